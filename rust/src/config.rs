@@ -1,4 +1,75 @@
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct Config {
     pub neutrino_path: Option<String>,
+    #[serde(default)]
+    pub model_paths: Vec<String>,
+    #[serde(default)]
+    pub timing: TimingConfig,
+    #[serde(default)]
+    pub score: ScoreConfig,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ScoreConfig {
+    pub min_rest_duration_ms: f64,
+    pub auto_breath: bool,
+    pub breath_min_gap_ms: f64,
+    pub breath_length_ms: f64,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        Self {
+            min_rest_duration_ms: 50.0,
+            auto_breath: false,
+            breath_min_gap_ms: 300.0,
+            breath_length_ms: 200.0,
+        }
+    }
+}
+
+impl ScoreConfig {
+    pub fn score_options(&self) -> crate::synthesizer::ScoreOptions {
+        crate::synthesizer::ScoreOptions {
+            min_rest_duration_ns: ms_to_ns(self.min_rest_duration_ms),
+            breath: self.auto_breath.then(|| crate::synthesizer::BreathOptions {
+                min_gap_ns: ms_to_ns(self.breath_min_gap_ms),
+                length_ns: ms_to_ns(self.breath_length_ms),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TimingConfig {
+    pub min_consonant_duration_ms: f64,
+    pub min_vowel_duration_ms: f64,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        Self {
+            min_consonant_duration_ms: 10.0,
+            min_vowel_duration_ms: 20.0,
+        }
+    }
+}
+
+impl TimingConfig {
+    pub fn normalize_options(&self) -> crate::timing::NormalizeOptions {
+        crate::timing::NormalizeOptions {
+            min_consonant_duration_ns: ms_to_ns(self.min_consonant_duration_ms),
+            min_vowel_duration_ns: ms_to_ns(self.min_vowel_duration_ms),
+        }
+    }
+}
+
+fn ms_to_ns(ms: f64) -> u64 {
+    if ms.is_finite() && ms > 0.0 {
+        (ms * 1_000_000.0).round() as u64
+    } else {
+        0
+    }
 }
@@ -0,0 +1,227 @@
+static DICTIONARY_FILE_NAMES: &[&str] = &["dictionary.toml", "dictionary.json"];
+
+// NOTE: 音素は空白区切りの文字列と配列のどちらでも書ける
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+enum DictionaryEntry {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl DictionaryEntry {
+    fn into_phonemes(self) -> Vec<String> {
+        match self {
+            DictionaryEntry::Text(text) => text.split_whitespace().map(str::to_string).collect(),
+            DictionaryEntry::List(list) => list
+                .iter()
+                .flat_map(|p| p.split_whitespace())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    entries: std::collections::HashMap<String, Vec<String>>,
+}
+
+impl Dictionary {
+    pub fn parse(path: &std::path::Path, content: &str) -> anyhow::Result<Self> {
+        let entries: std::collections::HashMap<String, DictionaryEntry> =
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("json") => serde_json::from_str(content).map_err(|e| {
+                    anyhow::anyhow!("Failed to parse dictionary {}: {}", path.display(), e)
+                })?,
+                _ => toml::from_str(content).map_err(|e| {
+                    anyhow::anyhow!("Failed to parse dictionary {}: {}", path.display(), e)
+                })?,
+            };
+        let mut dictionary = Self::default();
+        for (lyric, entry) in entries {
+            let phonemes = entry.into_phonemes();
+            if phonemes.is_empty() {
+                anyhow::bail!(
+                    "Dictionary entry {:?} in {} has no phonemes",
+                    lyric,
+                    path.display()
+                );
+            }
+            dictionary
+                .entries
+                .insert(lyric.trim().to_string(), phonemes);
+        }
+        Ok(dictionary)
+    }
+
+    pub fn get(&self, lyric: &str) -> Option<&[String]> {
+        self.entries.get(lyric.trim()).map(Vec::as_slice)
+    }
+
+    // NOTE: 後から重ねた辞書の項目が優先される
+    pub fn extend(&mut self, other: &Dictionary) {
+        self.entries
+            .extend(other.entries.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+pub fn dictionary_paths(directory: &std::path::Path) -> Vec<std::path::PathBuf> {
+    DICTIONARY_FILE_NAMES
+        .iter()
+        .map(|name| directory.join(name))
+        .collect()
+}
+
+// NOTE: 更新日時が変わったファイルだけ読み直すので、合成のたびに呼んでも編集がすぐ反映される
+#[derive(Debug, Default)]
+pub struct DictionaryCache {
+    files: std::collections::HashMap<
+        std::path::PathBuf,
+        (std::time::SystemTime, std::sync::Arc<Dictionary>),
+    >,
+}
+
+impl DictionaryCache {
+    pub fn load(&mut self, paths: &[std::path::PathBuf]) -> anyhow::Result<Dictionary> {
+        let mut merged = Dictionary::default();
+        for path in paths {
+            if let Some(dictionary) = self.load_file(path)? {
+                merged.extend(&dictionary);
+            }
+        }
+        Ok(merged)
+    }
+
+    fn load_file(
+        &mut self,
+        path: &std::path::Path,
+    ) -> anyhow::Result<Option<std::sync::Arc<Dictionary>>> {
+        let Ok(modified) = std::fs::metadata(path).and_then(|m| m.modified()) else {
+            self.files.remove(path);
+            return Ok(None);
+        };
+        if let Some((cached_modified, dictionary)) = self.files.get(path) {
+            if *cached_modified == modified {
+                return Ok(Some(dictionary.clone()));
+            }
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read dictionary {}: {}", path.display(), e))?;
+        let dictionary = std::sync::Arc::new(Dictionary::parse(path, &content)?);
+        self.files
+            .insert(path.to_path_buf(), (modified, dictionary.clone()));
+        Ok(Some(dictionary))
+    }
+}
+
+// NOTE: 辞書にある歌詞は組み込みの変換より優先し、モーラに分けずにひとつのノートで歌う
+#[derive(Debug)]
+pub struct DictionaryPhonemizer<'a> {
+    pub dictionary: &'a Dictionary,
+    pub inner: &'a dyn crate::phonemizer::Phonemizer,
+}
+
+impl crate::phonemizer::Phonemizer for DictionaryPhonemizer<'_> {
+    fn language(&self) -> &str {
+        self.inner.language()
+    }
+
+    fn phonemize(&self, lyric: &str) -> anyhow::Result<Vec<String>> {
+        match self.dictionary.get(lyric) {
+            Some(phonemes) => Ok(phonemes.to_vec()),
+            None => self.inner.phonemize(lyric),
+        }
+    }
+
+    fn split_syllables(&self, lyric: &str) -> anyhow::Result<Vec<String>> {
+        match self.dictionary.get(lyric) {
+            Some(_) => Ok(vec![lyric.to_string()]),
+            None => self.inner.split_syllables(lyric),
+        }
+    }
+
+    fn is_vowel(&self, phoneme: &str) -> bool {
+        self.inner.is_vowel(phoneme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phonemizer::Phonemizer;
+
+    #[test]
+    fn parses_toml_and_json_dictionaries() {
+        let toml = Dictionary::parse(
+            std::path::Path::new("dictionary.toml"),
+            "\"love\" = \"r a b u\"\n\"歌\" = [\"u\", \"t a\"]\n",
+        )
+        .expect("must parse");
+        assert_eq!(toml.get("love").expect("must exist"), ["r", "a", "b", "u"]);
+        assert_eq!(toml.get(" 歌 ").expect("must exist"), ["u", "t", "a"]);
+
+        let json = Dictionary::parse(
+            std::path::Path::new("dictionary.json"),
+            r#"{ "love": "r a v u" }"#,
+        )
+        .expect("must parse");
+        assert_eq!(json.get("love").expect("must exist"), ["r", "a", "v", "u"]);
+
+        assert!(
+            Dictionary::parse(std::path::Path::new("dictionary.toml"), "\"x\" = \"\"").is_err()
+        );
+        assert!(Dictionary::parse(std::path::Path::new("dictionary.json"), "[]").is_err());
+    }
+
+    #[test]
+    fn reloads_changed_files_and_layers_dictionaries() {
+        let directory = tempfile::tempdir().expect("must create");
+        let global = directory.path().join("dictionary.toml");
+        let voice = directory.path().join("dictionary.json");
+        std::fs::write(&global, "\"a\" = \"k a\"\n\"b\" = \"k i\"\n").expect("must write");
+        std::fs::write(&voice, r#"{ "b": "k u" }"#).expect("must write");
+
+        let mut cache = DictionaryCache::default();
+        let paths = [global.clone(), voice.clone()];
+        let dictionary = cache.load(&paths).expect("must load");
+        assert_eq!(dictionary.get("a").expect("must exist"), ["k", "a"]);
+        assert_eq!(dictionary.get("b").expect("must exist"), ["k", "u"]);
+
+        std::fs::write(&global, "\"a\" = \"s a\"\n").expect("must write");
+        let file = std::fs::File::options()
+            .write(true)
+            .open(&global)
+            .expect("must open");
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(10))
+            .expect("must touch");
+        std::fs::remove_file(&voice).expect("must remove");
+        let dictionary = cache.load(&paths).expect("must load");
+        assert_eq!(dictionary.get("a").expect("must exist"), ["s", "a"]);
+        assert!(dictionary.get("b").is_none());
+    }
+
+    #[test]
+    fn dictionary_takes_precedence_over_phonemizer() {
+        let dictionary = Dictionary::parse(
+            std::path::Path::new("dictionary.toml"),
+            "\"さくら\" = \"s a k U r a\"\n",
+        )
+        .expect("must parse");
+        let phonemizer = DictionaryPhonemizer {
+            dictionary: &dictionary,
+            inner: &crate::phonemizer::JapanesePhonemizer,
+        };
+        assert_eq!(
+            phonemizer.phonemize("さくら").expect("must phonemize"),
+            ["s", "a", "k", "U", "r", "a"]
+        );
+        assert_eq!(
+            phonemizer.split_syllables("さくら").expect("must split"),
+            ["さくら"]
+        );
+        assert_eq!(
+            phonemizer.phonemize("はな").expect("must phonemize"),
+            ["h", "a", "n", "a"]
+        );
+    }
+}
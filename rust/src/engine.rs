@@ -6,11 +6,193 @@ use std::os::windows::process::CommandExt;
 #[derive(Debug)]
 pub struct Engine {
     neutrino_path: std::path::PathBuf,
+    config: config::Config,
+    // NOTE: 合成中でも音素のプレビューや音源一覧を返せるよう、音源と歌詞まわりは別のロックで持つ
+    library: std::sync::Arc<std::sync::Mutex<VoiceLibrary>>,
     server: Option<std::process::Child>,
 }
 
+#[derive(Debug)]
+pub struct VoiceLibrary {
+    config_dir: std::path::PathBuf,
+    model_roots: Vec<std::path::PathBuf>,
+    phonemizers: crate::phonemizer::Registry,
+    dictionaries: crate::dictionary::DictionaryCache,
+    voices: Option<crate::speaker::VoiceScan>,
+}
+
 type WavData = (wav_io::header::WavHeader, Vec<f32>);
 
+struct SynthesisInput {
+    payload: crate::synthesizer::SynthesisTaskPayload,
+    score: crate::neutrino_score::Score,
+    tunelab_start_in_synthesis_time: f64,
+    property_schema: crate::properties::PropertySchema,
+    phonemizer: std::sync::Arc<dyn crate::phonemizer::Phonemizer>,
+}
+
+#[derive(Debug)]
+struct LyricContext {
+    phonemizer: std::sync::Arc<dyn crate::phonemizer::Phonemizer>,
+    dictionary: crate::dictionary::Dictionary,
+    inventory: Option<crate::phoneme_inventory::PhonemeInventory>,
+}
+
+impl LyricContext {
+    fn phonemizer(&self) -> crate::dictionary::DictionaryPhonemizer<'_> {
+        crate::dictionary::DictionaryPhonemizer {
+            dictionary: &self.dictionary,
+            inner: self.phonemizer.as_ref(),
+        }
+    }
+
+    // NOTE: 音源で使えない音素を含む歌詞はエラーにする
+    fn preview(&self, lyrics: &[String]) -> Vec<crate::synthesizer::PhonemePreview> {
+        crate::synthesizer::preview_lyrics(lyrics, &self.phonemizer())
+            .into_iter()
+            .map(|mut preview| {
+                let errors = self
+                    .inventory
+                    .iter()
+                    .flat_map(|inventory| {
+                        preview
+                            .phonemes
+                            .iter()
+                            .filter(|p| !inventory.contains(p))
+                            .map(|p| inventory.unknown_phoneme_message(p))
+                    })
+                    .collect::<Vec<_>>();
+                if !errors.is_empty() {
+                    preview.phonemes.clear();
+                    preview.error = Some(errors.join("\n"));
+                }
+                preview
+            })
+            .collect()
+    }
+}
+
+impl VoiceLibrary {
+    pub fn rescan_voices(&mut self) -> &crate::speaker::VoiceScan {
+        self.voices
+            .insert(crate::speaker::scan_model_roots(&self.model_roots))
+    }
+
+    pub fn load_voices(&mut self) -> &crate::speaker::VoiceScan {
+        if self.voices.is_none() {
+            self.rescan_voices();
+        }
+        self.voices.as_ref().unwrap()
+    }
+
+    // NOTE: 音源を探したときに読んだinfo.tomlなどを使う。見つからなければ新しく追加された音源かもしれないので一度だけ探し直す
+    fn voice(&mut self, voice_id: &str) -> anyhow::Result<&crate::speaker::VoiceSource> {
+        if self.load_voices().find(voice_id).is_none() {
+            self.rescan_voices();
+        }
+        self.load_voices()
+            .find(voice_id)
+            .ok_or_else(|| anyhow::anyhow!("Voice not found: {}", voice_id))
+    }
+
+    fn voice_path(&mut self, voice_id: &str) -> anyhow::Result<std::path::PathBuf> {
+        Ok(self.voice(voice_id)?.path().to_path_buf())
+    }
+
+    // NOTE: エディタから何度も呼ばれるので、音源は探し直さずに一覧から探す。空のIDは音源を選んでいないパートなので既定の定義を返す
+    pub fn property_schema(
+        &mut self,
+        voice_id: &str,
+    ) -> anyhow::Result<crate::properties::PropertySchema> {
+        let voices = self.load_voices();
+        let property_schema = if voice_id.is_empty() {
+            crate::properties::PropertySchema::default()
+        } else {
+            voices
+                .find(voice_id)
+                .ok_or_else(|| anyhow::anyhow!("Voice not found: {}", voice_id))?
+                .property_schema()
+        };
+        let voice_ids = voices
+            .voices
+            .iter()
+            .map(|voice| voice.id())
+            .collect::<Vec<_>>();
+        Ok(property_schema.with_voice_options(&voice_ids))
+    }
+
+    // NOTE: 合成では新しく追加された音源も使えるよう、見つからなければ探し直してから定義を返す
+    fn synthesis_property_schema(
+        &mut self,
+        voice_id: &str,
+    ) -> anyhow::Result<crate::properties::PropertySchema> {
+        self.voice(voice_id)?;
+        self.property_schema(voice_id)
+    }
+
+    fn load_lyric_context(&mut self, voice_id: &str) -> anyhow::Result<LyricContext> {
+        let voice = self.voice(voice_id)?;
+        let (voice_path, language) = (voice.path().to_path_buf(), voice.language().to_string());
+        let phonemizer = self.phonemizers.get(&language);
+        // NOTE: 音源ごとの辞書は共通の辞書より優先する
+        let dictionary = self.dictionaries.load(
+            &[
+                crate::dictionary::dictionary_paths(&self.config_dir),
+                crate::dictionary::dictionary_paths(&voice_path),
+            ]
+            .concat(),
+        )?;
+        let inventory =
+            crate::phoneme_inventory::PhonemeInventory::load(&voice_path, phonemizer.language())?;
+        Ok(LyricContext {
+            phonemizer,
+            dictionary,
+            inventory,
+        })
+    }
+
+    // NOTE: 楽譜は区間の音源の言語と辞書で音素にしているので、段ごとの音源は同じ言語で、その音素をすべて使えるものに限る
+    fn validate_stage_voices(
+        &mut self,
+        voice_id: &str,
+        language: &str,
+        stage_voice_ids: &[&str],
+        score: &crate::neutrino_score::Score,
+    ) -> anyhow::Result<()> {
+        for stage_voice_id in stage_voice_ids
+            .iter()
+            .unique()
+            .filter(|stage_voice_id| **stage_voice_id != voice_id)
+        {
+            let lyric_context = self.load_lyric_context(stage_voice_id)?;
+            if lyric_context.phonemizer.language() != language {
+                anyhow::bail!(
+                    "Voice {} ({}) cannot be used for a stage of {} ({})",
+                    stage_voice_id,
+                    lyric_context.phonemizer.language(),
+                    voice_id,
+                    language
+                );
+            }
+            if let Some(inventory) = &lyric_context.inventory {
+                inventory
+                    .validate(score)
+                    .map_err(|e| anyhow::anyhow!("Voice {}: {}", stage_voice_id, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    // NOTE: NEUTRINOを呼ばずに歌詞ごとの音素を返すので、入力中のプレビューに使える
+    pub fn preview_phonemes(
+        &mut self,
+        voice_id: &str,
+        lyrics: &[String],
+    ) -> anyhow::Result<Vec<crate::synthesizer::PhonemePreview>> {
+        Ok(self.load_lyric_context(voice_id)?.preview(lyrics))
+    }
+}
+
 impl Engine {
     pub fn new(dll_path: std::path::PathBuf) -> anyhow::Result<Self> {
         let config_path = dll_path.join("config.json");
@@ -67,8 +249,21 @@ impl Engine {
             ));
         }
 
+        let neutrino_path = std::path::PathBuf::from(neutrino_path);
+        // NOTE: NEUTRINOのmodelディレクトリに加えて、config.jsonのmodel_pathsも探す
+        let model_roots = std::iter::once(neutrino_path.join("model"))
+            .chain(config.model_paths.iter().map(std::path::PathBuf::from))
+            .collect();
         Ok(Self {
-            neutrino_path: config.neutrino_path.unwrap().into(),
+            neutrino_path,
+            config,
+            library: std::sync::Arc::new(std::sync::Mutex::new(VoiceLibrary {
+                config_dir: dll_path,
+                model_roots,
+                phonemizers: crate::phonemizer::Registry::default(),
+                dictionaries: crate::dictionary::DictionaryCache::default(),
+                voices: None,
+            })),
             server: None,
         })
     }
@@ -98,60 +293,144 @@ impl Engine {
         Ok(())
     }
 
-    pub fn load_voices(&self) -> anyhow::Result<Vec<crate::speaker::VoiceSource>> {
-        let mut speakers = Vec::new();
-        let models_path = self.neutrino_path.join("model");
-        if !models_path.exists() {
-            return Err(anyhow::anyhow!("Neutrino model directory not found"));
-        }
+    pub fn library(&self) -> std::sync::Arc<std::sync::Mutex<VoiceLibrary>> {
+        self.library.clone()
+    }
 
-        for entry in std::fs::read_dir(models_path)? {
-            let entry = entry?;
-            if entry.file_type()?.is_dir() {
-                match crate::speaker::VoiceSource::load(&entry.path()) {
-                    Ok(voice) => speakers.push(voice),
-                    Err(e) => eprintln!(
-                        "Failed to load voice from {}: {}",
-                        entry.path().display(),
-                        e
-                    ),
-                }
-            }
-        }
+    fn lock_library(&self) -> anyhow::Result<std::sync::MutexGuard<'_, VoiceLibrary>> {
+        self.library
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire voice library lock: {}", e))
+    }
 
-        Ok(speakers)
+    fn voice_path(&self, voice_id: &str) -> anyhow::Result<std::path::PathBuf> {
+        self.lock_library()?.voice_path(voice_id)
     }
 
     pub fn synthesize(&mut self, synthesis_task_json: &str) -> anyhow::Result<String> {
-        let (payload, score, tunelab_start_in_synthesis_time) =
-            Self::prepare_synthesis_input(synthesis_task_json)?;
+        let payload =
+            serde_json::from_str::<crate::synthesizer::SynthesisTaskPayload>(synthesis_task_json)
+                .map_err(|e| anyhow::anyhow!("Failed to parse synthesis task payload: {}", e))?;
+        let note_voices = self.note_voices(&payload)?;
+        let mut segments = crate::voice_switch::split_by_voice(&payload, &note_voices);
+        let response = if segments.len() == 1 {
+            self.synthesize_payload(segments.remove(0).payload)?
+        } else {
+            let responses = segments
+                .iter()
+                .map(|segment| self.synthesize_payload(segment.payload.clone()))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            crate::voice_switch::merge_responses(&segments, responses, payload.notes.len())?
+        };
 
-        let timings = self.synthesize_timing(&payload.voice_id, &score)?;
-        let mapped_phoneme_groups = self.map_phonemes_to_notes(&score, &timings)?;
+        Ok(serde_json::to_string(&response)?)
+    }
+
+    // NOTE: ノートのvoiceプロパティで指定された音源。なければパートの音源
+    fn note_voices(
+        &mut self,
+        payload: &crate::synthesizer::SynthesisTaskPayload,
+    ) -> anyhow::Result<Vec<String>> {
+        let property_schema = self
+            .lock_library()?
+            .synthesis_property_schema(&payload.voice_id)?;
+        Ok(payload
+            .notes
+            .iter()
+            .map(|note| {
+                property_schema
+                    .resolve_note(&note.properties)
+                    .text("voice")
+                    .filter(|voice| !voice.is_empty())
+                    .unwrap_or(&payload.voice_id)
+                    .to_string()
+            })
+            .collect())
+    }
+
+    fn synthesize_payload(
+        &mut self,
+        payload: crate::synthesizer::SynthesisTaskPayload,
+    ) -> anyhow::Result<crate::synthesizer::SynthesisResponse> {
+        let SynthesisInput {
+            payload,
+            score,
+            tunelab_start_in_synthesis_time,
+            property_schema,
+            phonemizer,
+        } = self.prepare_synthesis_input(payload)?;
+        let payload_note_indices = crate::synthesizer::payload_note_indices(&score);
+        let part_values = property_schema.resolve_part(&payload.part_properties);
+        let style_shift = part_values.number("styleShift").unwrap_or_default();
+        // NOTE: 段ごとに別の音源を指定できる。指定がなければその区間の音源
+        let stage_voice = |name: &str| {
+            part_values
+                .text(name)
+                .filter(|voice| !voice.is_empty())
+                .unwrap_or(&payload.voice_id)
+                .to_string()
+        };
+        let timing_voice = stage_voice("timingVoice");
+        let f0_voice = stage_voice("f0Voice");
+        let waveform_voice = stage_voice("waveformVoice");
+        self.lock_library()?.validate_stage_voices(
+            &payload.voice_id,
+            phonemizer.language(),
+            &[&timing_voice, &f0_voice, &waveform_voice],
+            &score,
+        )?;
+
+        let timings = self.synthesize_timing(&timing_voice, &score)?;
+        let mut mapped_phoneme_groups = self.map_phonemes_to_notes(&score, &timings)?;
+        crate::timing::scale_consonants(
+            &mut mapped_phoneme_groups,
+            &crate::neutrino_score::compute_score_time_ranges_ns(&score),
+            &Self::consonant_scales(
+                &payload,
+                &payload_note_indices,
+                &property_schema,
+                &part_values,
+            ),
+            phonemizer.as_ref(),
+        );
         let merged_phonemes = Self::merge_phonemes_with_payload(
             &payload,
+            &payload_note_indices,
             &mapped_phoneme_groups,
             tunelab_start_in_synthesis_time,
         );
+        let (merged_phonemes, timing_adjustments) = crate::timing::normalize_timings(
+            &merged_phonemes,
+            &mapped_phoneme_groups
+                .iter()
+                .map(|group| group.len())
+                .collect::<Vec<_>>(),
+            &crate::neutrino_score::compute_score_time_ranges_ns(&score),
+            &self.config.timing.normalize_options(),
+            phonemizer.as_ref(),
+        );
 
-        let style_score = Self::transpose_score_pitches(&score, payload.style_shift);
-        let inferred_f0_values =
-            self.synthesize_f0(&payload.voice_id, &style_score, &merged_phonemes)?;
+        let style_score = Self::transpose_score_pitches(&score, style_shift);
+        let inferred_f0_values = self.synthesize_f0(&f0_voice, &style_score, &merged_phonemes)?;
         // Infer f0 on style-shifted notes, then shift f0 back to the original key.
-        let f0_values = Self::shift_f0_by_semitones(&inferred_f0_values, -payload.style_shift);
+        let f0_values = Self::shift_f0_by_semitones(&inferred_f0_values, -style_shift);
 
         let mapped_f0_values = Self::apply_payload_pitch_to_f0(
             &payload.pitch,
             &f0_values,
             tunelab_start_in_synthesis_time,
         );
-        let shifted_mapped_f0_values =
-            Self::shift_f0_by_cents(&mapped_f0_values, payload.pitch_shift_cents);
+        let shifted_mapped_f0_values = Self::shift_f0_by_cents(
+            &mapped_f0_values,
+            part_values.number("pitchShiftCents").unwrap_or_default(),
+        );
 
-        let waveform_score =
-            Self::transpose_score_pitches(&style_score, payload.waveform_style_shift);
+        let waveform_score = Self::transpose_score_pitches(
+            &style_score,
+            part_values.number("waveformStyleShift").unwrap_or_default(),
+        );
         let wav_data = self.synthesize_waveform(
-            &payload.voice_id,
+            &waveform_voice,
             &waveform_score,
             &merged_phonemes,
             &shifted_mapped_f0_values,
@@ -159,13 +438,37 @@ impl Engine {
         let response = Self::build_synthesis_response(
             &payload,
             &shifted_mapped_f0_values,
+            &payload_note_indices,
             &mapped_phoneme_groups,
             &merged_phonemes,
+            &timing_adjustments,
             wav_data,
             tunelab_start_in_synthesis_time,
         );
 
-        Ok(serde_json::to_string(&response)?)
+        Ok(response)
+    }
+
+    fn consonant_scales(
+        payload: &crate::synthesizer::SynthesisTaskPayload,
+        payload_note_indices: &[Option<usize>],
+        property_schema: &crate::properties::PropertySchema,
+        part_values: &crate::properties::PropertyValues,
+    ) -> Vec<f64> {
+        let part_scale = part_values.number("consonantScale").unwrap_or(1.0);
+        // NOTE: 休符は1.0
+        payload_note_indices
+            .iter()
+            .map(|index| {
+                index.map_or(1.0, |i| {
+                    part_scale
+                        * property_schema
+                            .resolve_note(&payload.notes[i].properties)
+                            .number("consonantScale")
+                            .unwrap_or(1.0)
+                })
+            })
+            .collect()
     }
 
     fn transpose_score_pitches(
@@ -215,34 +518,54 @@ impl Engine {
     }
 
     fn prepare_synthesis_input(
-        synthesis_task_json: &str,
-    ) -> anyhow::Result<(
-        crate::synthesizer::SynthesisTaskPayload,
-        crate::neutrino_score::Score,
-        f64,
-    )> {
-        let payload =
-            serde_json::from_str::<crate::synthesizer::SynthesisTaskPayload>(synthesis_task_json)
-                .map_err(|e| anyhow::anyhow!("Failed to parse synthesis task payload: {}", e))?;
-        let score = crate::synthesizer::task_notes_to_score(&payload.notes)?;
+        &mut self,
+        mut payload: crate::synthesizer::SynthesisTaskPayload,
+    ) -> anyhow::Result<SynthesisInput> {
+        let mut library = self.lock_library()?;
+        let property_schema = library.synthesis_property_schema(&payload.voice_id)?;
+        payload.apply_part_properties(&property_schema.resolve_part(&payload.part_properties));
+        let lyric_context = library.load_lyric_context(&payload.voice_id)?;
+        drop(library);
+        let score = crate::synthesizer::task_notes_to_score(
+            &payload.notes,
+            &payload.tempos,
+            &payload.time_signatures,
+            &payload.key_signatures,
+            &self.config.score.score_options(),
+            &lyric_context.phonemizer(),
+        )?;
+        if let Some(inventory) = &lyric_context.inventory {
+            inventory.validate(&score)?;
+        }
+        let first_note = crate::synthesizer::payload_note_indices(&score)
+            .iter()
+            .position(|index| *index == Some(0))
+            .unwrap_or(1);
         let tunelab_start_in_synthesis_time =
-            (score.notes[1].start_time_ns as f64 / 1e9) - payload.notes[0].start_time;
-        Ok((payload, score, tunelab_start_in_synthesis_time))
+            (score.notes[first_note].start_time_ns as f64 / 1e9) - payload.notes[0].start_time;
+        Ok(SynthesisInput {
+            payload,
+            score,
+            tunelab_start_in_synthesis_time,
+            property_schema,
+            phonemizer: lyric_context.phonemizer,
+        })
     }
 
     fn merge_phonemes_with_payload(
         payload: &crate::synthesizer::SynthesisTaskPayload,
+        payload_note_indices: &[Option<usize>],
         mapped_phoneme_groups: &[Vec<crate::synthesizer::TimingLabel>],
         tunelab_start_in_synthesis_time: f64,
     ) -> Vec<crate::synthesizer::TimingLabel> {
         let mut merged_phonemes =
             Vec::with_capacity(payload.notes.iter().map(|n| n.phonemes.len()).sum());
 
-        // NOTE: pauが最初と最後にあるのでNoneではさむ
+        // NOTE: 休符はNone
         for (synthesized_phonemes, note) in mapped_phoneme_groups.iter().zip(
-            std::iter::once(None)
-                .chain(payload.notes.iter().map(Some))
-                .chain(std::iter::once(None)),
+            payload_note_indices
+                .iter()
+                .map(|index| index.map(|i| &payload.notes[i])),
         ) {
             match note {
                 Some(note) => {
@@ -318,6 +641,7 @@ impl Engine {
     }
 
     fn build_note_phonemes(
+        payload_note_indices: &[Option<usize>],
         mapped_phoneme_groups: &[Vec<crate::synthesizer::TimingLabel>],
         merged_phonemes: &[crate::synthesizer::TimingLabel],
         tunelab_start_in_synthesis_time: f64,
@@ -331,33 +655,32 @@ impl Engine {
                     .iter()
                     .filter_map(|_| merged_iter.next())
                     .collect::<Vec<_>>();
-                if i == 0 || i == mapped_phoneme_groups.len() - 1 {
-                    // 最初と最後のグループはpauなのでスキップ
-                    None
-                } else {
-                    Some(crate::synthesizer::NotePhonemes {
-                        note_index: i - 1,
-                        phonemes: current_group
-                            .iter()
-                            .map(|p| crate::synthesizer::SynthesizedPhoneme {
-                                start_time: (p.start_time_ns as f64) / 1e9
-                                    - tunelab_start_in_synthesis_time,
-                                end_time: (p.end_time_ns as f64) / 1e9
-                                    - tunelab_start_in_synthesis_time,
-                                symbol: p.phoneme.clone(),
-                            })
-                            .collect(),
-                    })
-                }
+                // 休符のグループはTuneLabのノートではないのでスキップ
+                payload_note_indices[i].map(|note_index| crate::synthesizer::NotePhonemes {
+                    note_index,
+                    phonemes: current_group
+                        .iter()
+                        .map(|p| crate::synthesizer::SynthesizedPhoneme {
+                            start_time: (p.start_time_ns as f64) / 1e9
+                                - tunelab_start_in_synthesis_time,
+                            end_time: (p.end_time_ns as f64) / 1e9
+                                - tunelab_start_in_synthesis_time,
+                            symbol: p.phoneme.clone(),
+                        })
+                        .collect(),
+                })
             })
             .collect()
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn build_synthesis_response(
         payload: &crate::synthesizer::SynthesisTaskPayload,
         f0_values: &[f32],
+        payload_note_indices: &[Option<usize>],
         mapped_phoneme_groups: &[Vec<crate::synthesizer::TimingLabel>],
         merged_phonemes: &[crate::synthesizer::TimingLabel],
+        timing_adjustments: &[crate::timing::Adjustment],
         wav_data: WavData,
         tunelab_start_in_synthesis_time: f64,
     ) -> crate::synthesizer::SynthesisResponse {
@@ -405,10 +728,32 @@ impl Engine {
                 .filter_map(|(&midi, &skipped)| if skipped { None } else { Some(midi) })
                 .collect(),
             note_phonemes: Self::build_note_phonemes(
+                payload_note_indices,
                 mapped_phoneme_groups,
                 merged_phonemes,
                 tunelab_start_in_synthesis_time,
             ),
+            timing_adjustments: timing_adjustments
+                .iter()
+                .map(|adjustment| crate::synthesizer::TimingAdjustment {
+                    // NOTE: 休符はTuneLabのノートではないのでNone
+                    note_index: payload_note_indices
+                        .get(adjustment.note_index)
+                        .copied()
+                        .flatten(),
+                    phoneme_index: adjustment.phoneme_index,
+                    symbol: adjustment.phoneme.clone(),
+                    kinds: adjustment.kinds.clone(),
+                    original_start_time: (adjustment.original_start_time_ns as f64) / 1e9
+                        - tunelab_start_in_synthesis_time,
+                    original_end_time: (adjustment.original_end_time_ns as f64) / 1e9
+                        - tunelab_start_in_synthesis_time,
+                    start_time: (adjustment.start_time_ns as f64) / 1e9
+                        - tunelab_start_in_synthesis_time,
+                    end_time: (adjustment.end_time_ns as f64) / 1e9
+                        - tunelab_start_in_synthesis_time,
+                })
+                .collect(),
             note_count: payload.notes.len(),
             phoneme_count: merged_phonemes.len(),
             property_count: 0, // 今のところプロパティは返さない
@@ -420,20 +765,14 @@ impl Engine {
         voice_id: &str,
         score: &crate::neutrino_score::Score,
     ) -> anyhow::Result<Vec<crate::synthesizer::TimingLabel>> {
+        let voice_path = self.voice_path(voice_id)?;
         let label_file = tempfile::NamedTempFile::new()
             .map_err(|e| anyhow::anyhow!("Failed to create temporary label file: {}", e))?;
         let label_path = label_file.path().to_string_lossy().to_string();
-        for label in crate::neutrino_score::compose_labels_from_score(score)? {
-            // HTS label timing uses 100ns units.
-            let start_time_100ns = label.start_time_ns / 100;
-            let end_time_100ns = label.end_time_ns / 100;
-            writeln!(
-                &label_file,
-                "{} {} {}",
-                start_time_100ns, end_time_100ns, label.label,
-            )
-            .map_err(|e| anyhow::anyhow!("Failed to write to label file: {}", e))?;
-        }
+        crate::label_file::save_full_context(
+            label_file.path(),
+            &crate::neutrino_score::compose_labels_from_score(score)?,
+        )?;
         let generated_label_file = tempfile::NamedTempFile::new().map_err(|e| {
             anyhow::anyhow!("Failed to create temporary generated label file: {}", e)
         })?;
@@ -453,11 +792,7 @@ impl Engine {
             f0_path.as_str(),
             melspec_path.as_str(),
             generated_wav_path.as_str(),
-            self.neutrino_path
-                .join("model")
-                .join(voice_id)
-                .to_str()
-                .unwrap(),
+            voice_path.to_str().unwrap(),
             "-n",
             num_cpus::get().to_string().as_str(),
             "-m",
@@ -466,11 +801,7 @@ impl Engine {
             "--skip-f0",
             "--skip-wav",
         ])?;
-        let label_data = std::fs::read_to_string(generated_label_path)
-            .map_err(|e| anyhow::anyhow!("Failed to read generated label file: {}", e))?;
-        let labels = crate::synthesizer::parse_timing_label_file(&label_data)?;
-
-        Ok(labels)
+        crate::label_file::load_mono(generated_label_file.path())
     }
 
     fn map_phonemes_to_notes(
@@ -504,34 +835,19 @@ impl Engine {
         score: &crate::neutrino_score::Score,
         timings: &[crate::synthesizer::TimingLabel],
     ) -> anyhow::Result<Vec<f32>> {
+        let voice_path = self.voice_path(voice_id)?;
         let label_file = tempfile::NamedTempFile::new()
             .map_err(|e| anyhow::anyhow!("Failed to create temporary label file: {}", e))?;
         let label_path = label_file.path().to_string_lossy().to_string();
-        for label in crate::neutrino_score::compose_labels_from_score(score)? {
-            // HTS label timing uses 100ns units.
-            let start_time_100ns = label.start_time_ns / 100;
-            let end_time_100ns = label.end_time_ns / 100;
-            writeln!(
-                &label_file,
-                "{} {} {}",
-                start_time_100ns, end_time_100ns, label.label,
-            )
-            .map_err(|e| anyhow::anyhow!("Failed to write to label file: {}", e))?;
-        }
+        crate::label_file::save_full_context(
+            label_file.path(),
+            &crate::neutrino_score::compose_labels_from_score(score)?,
+        )?;
         let generated_label_file = tempfile::NamedTempFile::new().map_err(|e| {
             anyhow::anyhow!("Failed to create temporary generated label file: {}", e)
         })?;
         let generated_label_path = generated_label_file.path().to_string_lossy().to_string();
-        for label in timings {
-            let start_time_100ns = label.start_time_ns / 100;
-            let end_time_100ns = label.end_time_ns / 100;
-            writeln!(
-                &generated_label_file,
-                "{} {} {}",
-                start_time_100ns, end_time_100ns, label.phoneme,
-            )
-            .map_err(|e| anyhow::anyhow!("Failed to write to generated label file: {}", e))?;
-        }
+        crate::label_file::save_mono(generated_label_file.path(), timings)?;
         let f0_file = tempfile::NamedTempFile::new()
             .map_err(|e| anyhow::anyhow!("Failed to create temporary f0 file: {}", e))?;
         let f0_path = f0_file.path().to_string_lossy().to_string();
@@ -547,11 +863,7 @@ impl Engine {
             f0_path.as_str(),
             melspec_path.as_str(),
             generated_wav_path.as_str(),
-            self.neutrino_path
-                .join("model")
-                .join(voice_id)
-                .to_str()
-                .unwrap(),
+            voice_path.to_str().unwrap(),
             "-n",
             num_cpus::get().to_string().as_str(),
             "-m",
@@ -579,34 +891,19 @@ impl Engine {
         timings: &[crate::synthesizer::TimingLabel],
         f0_values: &[f32],
     ) -> anyhow::Result<WavData> {
+        let voice_path = self.voice_path(voice_id)?;
         let label_file = tempfile::NamedTempFile::new()
             .map_err(|e| anyhow::anyhow!("Failed to create temporary label file: {}", e))?;
         let label_path = label_file.path().to_string_lossy().to_string();
-        for label in crate::neutrino_score::compose_labels_from_score(score)? {
-            // HTS label timing uses 100ns units.
-            let start_time_100ns = label.start_time_ns / 100;
-            let end_time_100ns = label.end_time_ns / 100;
-            writeln!(
-                &label_file,
-                "{} {} {}",
-                start_time_100ns, end_time_100ns, label.label,
-            )
-            .map_err(|e| anyhow::anyhow!("Failed to write to label file: {}", e))?;
-        }
+        crate::label_file::save_full_context(
+            label_file.path(),
+            &crate::neutrino_score::compose_labels_from_score(score)?,
+        )?;
         let generated_label_file = tempfile::NamedTempFile::new().map_err(|e| {
             anyhow::anyhow!("Failed to create temporary generated label file: {}", e)
         })?;
         let generated_label_path = generated_label_file.path().to_string_lossy().to_string();
-        for label in timings {
-            let start_time_100ns = label.start_time_ns / 100;
-            let end_time_100ns = label.end_time_ns / 100;
-            writeln!(
-                &generated_label_file,
-                "{} {} {}",
-                start_time_100ns, end_time_100ns, label.phoneme,
-            )
-            .map_err(|e| anyhow::anyhow!("Failed to write to generated label file: {}", e))?;
-        }
+        crate::label_file::save_mono(generated_label_file.path(), timings)?;
         let f0_file = tempfile::NamedTempFile::new()
             .map_err(|e| anyhow::anyhow!("Failed to create temporary f0 file: {}", e))?;
         let f0_path = f0_file.path().to_string_lossy().to_string();
@@ -631,11 +928,7 @@ impl Engine {
             f0_path.as_str(),
             melspec_path.as_str(),
             generated_wav_path.as_str(),
-            self.neutrino_path
-                .join("model")
-                .join(voice_id)
-                .to_str()
-                .unwrap(),
+            voice_path.to_str().unwrap(),
             "-n",
             num_cpus::get().to_string().as_str(),
             "-m",
@@ -702,3 +995,101 @@ impl Drop for Engine {
         self.shutdown();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_voice(root: &std::path::Path, id: &str, language: &str, phonemes: Option<&str>) {
+        let voice_path = root.join(id);
+        std::fs::create_dir(&voice_path).expect("must create");
+        std::fs::write(voice_path.join("model.bin"), b"").expect("must write");
+        std::fs::write(
+            voice_path.join("info.toml"),
+            format!(
+                "[speaker]\nname = \"{}\"\nlanguage = \"{}\"\n",
+                id, language
+            ),
+        )
+        .expect("must write");
+        if let Some(phonemes) = phonemes {
+            std::fs::write(voice_path.join("phonemes.txt"), phonemes).expect("must write");
+        }
+    }
+
+    #[test]
+    fn validates_stage_voices() {
+        let directory = tempfile::tempdir().expect("must create");
+        write_voice(directory.path(), "JA", "ja", None);
+        write_voice(directory.path(), "JA_SMALL", "ja", Some("pau sil a"));
+        write_voice(directory.path(), "EN", "en", None);
+        let mut library = VoiceLibrary {
+            config_dir: directory.path().to_path_buf(),
+            model_roots: vec![directory.path().to_path_buf()],
+            phonemizers: crate::phonemizer::Registry::default(),
+            dictionaries: crate::dictionary::DictionaryCache::default(),
+            voices: None,
+        };
+        let lyric_context = library.load_lyric_context("JA").expect("must load");
+        let notes = serde_json::from_str::<Vec<crate::synthesizer::SynthesisNotePayload>>(
+            r#"[{ "startTime": 0.0, "endTime": 0.5, "pitch": 60, "lyric": "か", "properties": {}, "phonemes": [] }]"#,
+        )
+        .expect("must parse");
+        let score = crate::synthesizer::task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &[],
+            &crate::synthesizer::ScoreOptions::default(),
+            &lyric_context.phonemizer(),
+        )
+        .expect("must convert");
+
+        library
+            .validate_stage_voices("JA", "JPN", &["JA", "JA", "JA"], &score)
+            .expect("same voice must pass");
+        let error = library
+            .validate_stage_voices("JA", "JPN", &["JA", "EN", "JA"], &score)
+            .expect_err("other language must fail");
+        assert!(error.to_string().contains("EN (ENG)"), "{}", error);
+        let error = library
+            .validate_stage_voices("JA", "JPN", &["JA_SMALL", "JA", "JA"], &score)
+            .expect_err("missing phoneme must fail");
+        assert!(error.to_string().contains("JA_SMALL"), "{}", error);
+        assert!(error.to_string().contains("note 0"), "{}", error);
+    }
+
+    #[test]
+    fn property_schema_does_not_rescan() {
+        let directory = tempfile::tempdir().expect("must create");
+        write_voice(directory.path(), "JA", "ja", None);
+        let mut library = VoiceLibrary {
+            config_dir: directory.path().to_path_buf(),
+            model_roots: vec![directory.path().to_path_buf()],
+            phonemizers: crate::phonemizer::Registry::default(),
+            dictionaries: crate::dictionary::DictionaryCache::default(),
+            voices: None,
+        };
+        let voice_options = |schema: &crate::properties::PropertySchema| {
+            serde_json::to_value(schema).expect("must serialize")["noteProperties"]
+                .as_array()
+                .expect("must be an array")
+                .iter()
+                .find(|property| property["name"] == "voice")
+                .map(|property| property["options"].clone())
+        };
+        let schema = library.property_schema("").expect("must load");
+        assert_eq!(voice_options(&schema), Some(serde_json::json!(["", "JA"])));
+
+        write_voice(directory.path(), "NEW", "ja", None);
+        assert!(library.property_schema("NEW").is_err());
+        assert_eq!(
+            voice_options(&library.property_schema("").expect("must load")),
+            Some(serde_json::json!(["", "JA"]))
+        );
+        library
+            .synthesis_property_schema("NEW")
+            .expect("synthesis must rescan");
+        library.property_schema("NEW").expect("must load");
+    }
+}
@@ -0,0 +1,364 @@
+use wana_kana::ConvertJapanese;
+
+pub static PHONEMES: &[&str] = &[
+    "a", "i", "u", "e", "o", "ky", "k", "sh", "s", "ch", "t", "ts", "n", "ny", "h", "hy", "m", "f",
+    "y", "my", "r", "ry", "w", "gy", "j", "g", "by", "z", "py", "d", "v", "b", "p", "dy", "N",
+    "cl", "br", "pau", "sil",
+];
+
+static MORAE: &[(&str, &[&str])] = &[
+    ("ア", &["a"]),
+    ("イ", &["i"]),
+    ("ウ", &["u"]),
+    ("エ", &["e"]),
+    ("オ", &["o"]),
+    ("キャ", &["ky", "a"]),
+    ("キュ", &["ky", "u"]),
+    ("キェ", &["ky", "e"]),
+    ("キョ", &["ky", "o"]),
+    ("カ", &["k", "a"]),
+    ("キ", &["k", "i"]),
+    ("ク", &["k", "u"]),
+    ("ケ", &["k", "e"]),
+    ("コ", &["k", "o"]),
+    ("シャ", &["sh", "a"]),
+    ("スィ", &["s", "i"]),
+    ("シュ", &["sh", "u"]),
+    ("シェ", &["sh", "e"]),
+    ("ショ", &["sh", "o"]),
+    ("サ", &["s", "a"]),
+    ("シ", &["sh", "i"]),
+    ("ス", &["s", "u"]),
+    ("セ", &["s", "e"]),
+    ("ソ", &["s", "o"]),
+    ("チャ", &["ch", "a"]),
+    ("チュ", &["ch", "u"]),
+    ("チェ", &["ch", "e"]),
+    ("チョ", &["ch", "o"]),
+    ("タ", &["t", "a"]),
+    ("チ", &["ch", "i"]),
+    ("ツ", &["ts", "u"]),
+    ("テ", &["t", "e"]),
+    ("ト", &["t", "o"]),
+    ("ツァ", &["ts", "a"]),
+    ("ツィ", &["ts", "i"]),
+    ("ツェ", &["ts", "e"]),
+    ("ツォ", &["ts", "o"]),
+    ("ナ", &["n", "a"]),
+    ("ニ", &["n", "i"]),
+    ("ヌ", &["n", "u"]),
+    ("ネ", &["n", "e"]),
+    ("ノ", &["n", "o"]),
+    ("ニャ", &["ny", "a"]),
+    ("ニュ", &["ny", "u"]),
+    ("ニェ", &["ny", "e"]),
+    ("ニョ", &["ny", "o"]),
+    ("ハ", &["h", "a"]),
+    ("ヒ", &["h", "i"]),
+    ("フ", &["h", "u"]),
+    ("ヘ", &["h", "e"]),
+    ("ホ", &["h", "o"]),
+    ("ヒャ", &["hy", "a"]),
+    ("ヒュ", &["hy", "u"]),
+    ("ヒェ", &["hy", "e"]),
+    ("ヒョ", &["hy", "o"]),
+    ("マ", &["m", "a"]),
+    ("ミ", &["m", "i"]),
+    ("ム", &["m", "u"]),
+    ("メ", &["m", "e"]),
+    ("モ", &["m", "o"]),
+    ("ファ", &["f", "a"]),
+    ("フィ", &["f", "i"]),
+    ("フェ", &["f", "e"]),
+    ("フォ", &["f", "o"]),
+    ("ヤ", &["y", "a"]),
+    ("ユ", &["y", "u"]),
+    ("イェ", &["y", "e"]),
+    ("ヨ", &["y", "o"]),
+    ("ミャ", &["my", "a"]),
+    ("ミュ", &["my", "u"]),
+    ("ミェ", &["my", "e"]),
+    ("ミョ", &["my", "o"]),
+    ("ラ", &["r", "a"]),
+    ("リ", &["r", "i"]),
+    ("ル", &["r", "u"]),
+    ("レ", &["r", "e"]),
+    ("ロ", &["r", "o"]),
+    ("リャ", &["ry", "a"]),
+    ("リュ", &["ry", "u"]),
+    ("リェ", &["ry", "e"]),
+    ("リョ", &["ry", "o"]),
+    ("ワ", &["w", "a"]),
+    ("ヲ", &["o"]),
+    ("ギャ", &["gy", "a"]),
+    ("ギュ", &["gy", "u"]),
+    ("ギェ", &["gy", "e"]),
+    ("ギョ", &["gy", "o"]),
+    ("ジャ", &["j", "a"]),
+    ("ジュ", &["j", "u"]),
+    ("ジェ", &["j", "e"]),
+    ("ジョ", &["j", "o"]),
+    ("ガ", &["g", "a"]),
+    ("ギ", &["g", "i"]),
+    ("グ", &["g", "u"]),
+    ("ゲ", &["g", "e"]),
+    ("ゴ", &["g", "o"]),
+    ("ビャ", &["by", "a"]),
+    ("ビュ", &["by", "u"]),
+    ("ビェ", &["by", "e"]),
+    ("ビョ", &["by", "o"]),
+    ("ザ", &["z", "a"]),
+    ("ジ", &["j", "i"]),
+    ("ズ", &["z", "u"]),
+    ("ゼ", &["z", "e"]),
+    ("ゾ", &["z", "o"]),
+    ("ピャ", &["py", "a"]),
+    ("ピュ", &["py", "u"]),
+    ("ピェ", &["py", "e"]),
+    ("ピョ", &["py", "o"]),
+    ("ダ", &["d", "a"]),
+    ("ヂ", &["j", "i"]),
+    ("ヅ", &["z", "u"]),
+    ("デ", &["d", "e"]),
+    ("ド", &["d", "o"]),
+    ("ヴァ", &["v", "a"]),
+    ("ヴィ", &["v", "i"]),
+    ("ヴ", &["v", "u"]),
+    ("ヴェ", &["v", "e"]),
+    ("ヴォ", &["v", "o"]),
+    ("バ", &["b", "a"]),
+    ("ビ", &["b", "i"]),
+    ("ブ", &["b", "u"]),
+    ("ベ", &["b", "e"]),
+    ("ボ", &["b", "o"]),
+    ("ウィ", &["w", "i"]),
+    ("ウェ", &["w", "e"]),
+    ("ウォ", &["w", "o"]),
+    ("パ", &["p", "a"]),
+    ("ピ", &["p", "i"]),
+    ("プ", &["p", "u"]),
+    ("ペ", &["p", "e"]),
+    ("ポ", &["p", "o"]),
+    ("ティ", &["t", "i"]),
+    ("ディ", &["d", "i"]),
+    ("デュ", &["dy", "u"]),
+    ("トゥ", &["t", "u"]),
+    ("ドゥ", &["d", "u"]),
+    ("ン", &["N"]),
+    ("ッ", &["cl"]),
+    ("ズィ", &["z", "i"]),
+];
+
+fn lookup(mora: &str) -> Option<&'static [&'static str]> {
+    MORAE
+        .iter()
+        .find(|(kana, _)| *kana == mora)
+        .map(|(_, phonemes)| *phonemes)
+}
+
+// NOTE: ひらがな・ローマ字はカタカナに、歴史的仮名遣いと小書きの「ヵ」「ヶ」は現代の表記にそろえる
+fn normalize(lyric: &str) -> String {
+    let mut normalized = String::new();
+    for c in lyric.trim().to_lowercase().to_katakana().chars() {
+        match c {
+            'ヰ' => normalized.push('イ'),
+            'ヱ' => normalized.push('エ'),
+            'ヵ' => normalized.push('カ'),
+            'ヶ' => normalized.push('ケ'),
+            'ヷ' => normalized.push_str("ヴァ"),
+            'ヸ' => normalized.push_str("ヴィ"),
+            'ヹ' => normalized.push_str("ヴェ"),
+            'ヺ' => normalized.push_str("ヴォ"),
+            '〜' | '～' => normalized.push('ー'),
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+// NOTE: 単独で現れた小書きの仮名は大きい仮名として読む
+fn enlarge_small_kana(c: char) -> Option<char> {
+    Some(match c {
+        'ァ' => 'ア',
+        'ィ' => 'イ',
+        'ゥ' => 'ウ',
+        'ェ' => 'エ',
+        'ォ' => 'オ',
+        'ャ' => 'ヤ',
+        'ュ' => 'ユ',
+        'ョ' => 'ヨ',
+        'ヮ' => 'ワ',
+        _ => return None,
+    })
+}
+
+fn vowel_mora(phoneme: &str) -> Option<&'static str> {
+    Some(match phoneme {
+        "a" => "ア",
+        "i" => "イ",
+        "u" => "ウ",
+        "e" => "エ",
+        "o" => "オ",
+        "N" => "ン",
+        _ => return None,
+    })
+}
+
+// NOTE: ローマ字として読めない音素名。これ以外の音素名（"n"など）はローマ字として読めなかったときだけそのまま使う
+static NON_ROMAJI_PHONEMES: &[&str] = &["N", "cl", "br", "pau", "sil"];
+
+pub fn lyric_to_morae(lyric: &str) -> anyhow::Result<Vec<String>> {
+    let trimmed = lyric.trim();
+    if NON_ROMAJI_PHONEMES.contains(&trimmed) {
+        return Ok(vec![trimmed.to_string()]);
+    }
+    match kana_to_morae(trimmed, lyric) {
+        Err(_) if PHONEMES.contains(&trimmed) => Ok(vec![trimmed.to_string()]),
+        result => result,
+    }
+}
+
+fn kana_to_morae(trimmed: &str, lyric: &str) -> anyhow::Result<Vec<String>> {
+    let chars = normalize(trimmed).chars().collect::<Vec<_>>();
+    if chars.is_empty() {
+        anyhow::bail!("Empty lyric");
+    }
+
+    let mut morae: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == 'ー' {
+            // NOTE: 長音は直前の母音を伸ばす
+            let vowel = morae
+                .last()
+                .and_then(|mora| lookup(mora))
+                .and_then(|phonemes| phonemes.last())
+                .and_then(|phoneme| vowel_mora(phoneme))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Long vowel mark without a preceding vowel: {}", lyric)
+                })?;
+            morae.push(vowel.to_string());
+            i += 1;
+            continue;
+        }
+        if let Some(pair) = chars
+            .get(i..i + 2)
+            .map(|pair| pair.iter().collect::<String>())
+            .filter(|pair| lookup(pair).is_some())
+        {
+            morae.push(pair);
+            i += 2;
+            continue;
+        }
+        let single = enlarge_small_kana(chars[i]).unwrap_or(chars[i]).to_string();
+        if lookup(&single).is_none() {
+            anyhow::bail!("Unsupported mora: {} (in lyric {})", single, lyric);
+        }
+        morae.push(single);
+        i += 1;
+    }
+    Ok(morae)
+}
+
+pub fn mora_to_phonemes(mora: &str) -> anyhow::Result<Vec<String>> {
+    if PHONEMES.contains(&mora) {
+        return Ok(vec![mora.to_string()]);
+    }
+    lookup(mora)
+        .map(|phonemes| phonemes.iter().map(|p| p.to_string()).collect())
+        .ok_or_else(|| anyhow::anyhow!("Unsupported mora: {}", mora))
+}
+
+pub fn lyric_to_phonemes(lyric: &str) -> anyhow::Result<Vec<String>> {
+    let mut phonemes = Vec::new();
+    for mora in lyric_to_morae(lyric)? {
+        phonemes.extend(mora_to_phonemes(&mora)?);
+    }
+    Ok(phonemes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_mora_in_the_table() {
+        for (kana, phonemes) in MORAE {
+            assert!(
+                phonemes.iter().all(|p| PHONEMES.contains(p)),
+                "{kana} has unknown phonemes"
+            );
+            assert_eq!(
+                lyric_to_morae(kana).expect("must tokenize"),
+                [kana.to_string()],
+                "{kana}"
+            );
+            assert_eq!(
+                lyric_to_phonemes(kana).expect("must convert"),
+                *phonemes,
+                "{kana}"
+            );
+            assert_eq!(
+                lyric_to_phonemes(&kana.to_hiragana()).expect("must convert"),
+                *phonemes,
+                "{kana} (hiragana)"
+            );
+        }
+    }
+
+    #[test]
+    fn multi_mora_lyrics() {
+        assert_eq!(
+            lyric_to_morae("さくら").expect("must tokenize"),
+            ["サ", "ク", "ラ"]
+        );
+        assert_eq!(
+            lyric_to_phonemes("きゃっと").expect("must convert"),
+            ["ky", "a", "cl", "t", "o"]
+        );
+        assert_eq!(
+            lyric_to_phonemes("ヴァイオリン").expect("must convert"),
+            ["v", "a", "i", "o", "r", "i", "N"]
+        );
+    }
+
+    #[test]
+    fn romaji_lyrics() {
+        assert_eq!(
+            lyric_to_phonemes("sakura").expect("must convert"),
+            ["s", "a", "k", "u", "r", "a"]
+        );
+        assert_eq!(
+            lyric_to_phonemes("Shinju").expect("must convert"),
+            ["sh", "i", "N", "j", "u"]
+        );
+        assert_eq!(lyric_to_phonemes("kyo").expect("must convert"), ["ky", "o"]);
+    }
+
+    #[test]
+    fn normalizes_long_vowels_and_variants() {
+        assert_eq!(lyric_to_morae("らー").expect("must tokenize"), ["ラ", "ア"]);
+        assert_eq!(
+            lyric_to_morae("きょ〜").expect("must tokenize"),
+            ["キョ", "オ"]
+        );
+        assert_eq!(lyric_to_phonemes("ゐゑ").expect("must convert"), ["i", "e"]);
+        assert_eq!(lyric_to_phonemes("ヶ").expect("must convert"), ["k", "e"]);
+        assert_eq!(lyric_to_phonemes("ヷ").expect("must convert"), ["v", "a"]);
+        assert_eq!(lyric_to_phonemes("ぁ").expect("must convert"), ["a"]);
+        assert!(lyric_to_morae("ー").is_err());
+        assert!(lyric_to_morae("ッー").is_err());
+    }
+
+    #[test]
+    fn phoneme_symbols_and_errors() {
+        assert_eq!(lyric_to_phonemes("br").expect("must convert"), ["br"]);
+        assert_eq!(lyric_to_phonemes("N").expect("must convert"), ["N"]);
+        assert_eq!(lyric_to_phonemes("n").expect("must convert"), ["N"]);
+        assert_eq!(lyric_to_phonemes("pau").expect("must convert"), ["pau"]);
+        assert_eq!(lyric_to_phonemes("sh").expect("must convert"), ["sh"]);
+        assert_eq!(lyric_to_phonemes("a").expect("must convert"), ["a"]);
+        assert!(lyric_to_phonemes("").is_err());
+        assert!(lyric_to_phonemes("漢字").is_err());
+    }
+}
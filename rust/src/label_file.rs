@@ -0,0 +1,210 @@
+// NOTE: .labの時刻はHTSにならって100ns単位。読み込んだらns単位に直す
+const TIME_UNIT_NS: u64 = 100;
+
+fn parse_lines<T>(
+    content: &str,
+    mut parse: impl FnMut(u64, u64, &str) -> anyhow::Result<T>,
+) -> anyhow::Result<Vec<T>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let line_number = i + 1;
+            let mut rest = line.trim();
+            let mut time = |name: &str| {
+                let (value, remaining) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                rest = remaining.trim_start();
+                let time = value.parse::<u64>().map_err(|e| {
+                    anyhow::anyhow!("line {}: invalid {} {:?}: {}", line_number, name, value, e)
+                })?;
+                time.checked_mul(TIME_UNIT_NS).ok_or_else(|| {
+                    anyhow::anyhow!("line {}: {} {} is too large", line_number, name, value)
+                })
+            };
+            let start_time_ns = time("start time")?;
+            let end_time_ns = time("end time")?;
+            if end_time_ns < start_time_ns {
+                anyhow::bail!(
+                    "line {}: end time {} is before start time {}",
+                    line_number,
+                    end_time_ns / TIME_UNIT_NS,
+                    start_time_ns / TIME_UNIT_NS
+                );
+            }
+            let label = rest;
+            if label.is_empty() {
+                anyhow::bail!("line {}: missing label", line_number);
+            }
+            parse(start_time_ns, end_time_ns, label)
+                .map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))
+        })
+        .collect()
+}
+
+fn format_line(start_time_ns: u64, end_time_ns: u64, label: impl std::fmt::Display) -> String {
+    format!(
+        "{} {} {}\n",
+        start_time_ns / TIME_UNIT_NS,
+        end_time_ns / TIME_UNIT_NS,
+        label
+    )
+}
+
+// NOTE: 今の合成の流れではフルコンテキストラベルを読み戻さないが、.labの読み書きは両方の形式でそろえておく
+#[cfg_attr(not(test), expect(dead_code))]
+pub fn parse_full_context(content: &str) -> anyhow::Result<Vec<crate::neutrino_score::TimedLabel>> {
+    parse_lines(content, |start_time_ns, end_time_ns, label| {
+        let label = crate::neutrino_label::parse_label_line(label)?;
+        label.validate()?;
+        Ok(crate::neutrino_score::TimedLabel {
+            label,
+            start_time_ns,
+            end_time_ns,
+        })
+    })
+}
+
+pub fn format_full_context(labels: &[crate::neutrino_score::TimedLabel]) -> String {
+    labels
+        .iter()
+        .map(|label| format_line(label.start_time_ns, label.end_time_ns, &label.label))
+        .collect()
+}
+
+pub fn parse_mono(content: &str) -> anyhow::Result<Vec<crate::synthesizer::TimingLabel>> {
+    parse_lines(content, |start_time_ns, end_time_ns, phoneme| {
+        if phoneme.contains(char::is_whitespace) {
+            anyhow::bail!("phoneme {:?} contains whitespace", phoneme);
+        }
+        Ok(crate::synthesizer::TimingLabel {
+            start_time_ns,
+            end_time_ns,
+            phoneme: phoneme.to_string(),
+        })
+    })
+}
+
+pub fn format_mono(labels: &[crate::synthesizer::TimingLabel]) -> String {
+    labels
+        .iter()
+        .map(|label| format_line(label.start_time_ns, label.end_time_ns, &label.phoneme))
+        .collect()
+}
+
+pub fn save_full_context(
+    path: &std::path::Path,
+    labels: &[crate::neutrino_score::TimedLabel],
+) -> anyhow::Result<()> {
+    // NOTE: 壊れたラベルをNeutrinoに渡すと原因のわかりにくいエラーになるので、書き出す前に検証する
+    for (i, label) in labels.iter().enumerate() {
+        label
+            .label
+            .validate()
+            .map_err(|e| anyhow::anyhow!("label {}: {}", i + 1, e))?;
+    }
+    std::fs::write(path, format_full_context(labels))
+        .map_err(|e| anyhow::anyhow!("Failed to write label file {}: {}", path.display(), e))
+}
+
+pub fn save_mono(
+    path: &std::path::Path,
+    labels: &[crate::synthesizer::TimingLabel],
+) -> anyhow::Result<()> {
+    std::fs::write(path, format_mono(labels))
+        .map_err(|e| anyhow::anyhow!("Failed to write label file {}: {}", path.display(), e))
+}
+
+pub fn load_mono(path: &std::path::Path) -> anyhow::Result<Vec<crate::synthesizer::TimingLabel>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read label file {}: {}", path.display(), e))?;
+    parse_mono(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse label file {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FULL_CONTEXT: &str = "0 5000000 p@xx^xx-pau+r=a_xx%xx^00_00~00-1!1[xx$xx]xx/A:xx-xx-xx@xx~xx/B:1_1_1@xx|xx/C:2+1+1@JPN&0/D:xx!xx#xx$xx%xx|xx&xx;xx-xx/E:xx]xx^0=4/4~100!1@240#96+xx]1$1|0[24&0]96=0^100~xx#xx_xx;xx$xx&xx%xx[xx|0]0-n^xx+xx~xx=xx@xx$xx!xx%xx#xx|xx|xx-xx&xx&xx+xx[xx;xx]xx;xx~xx~xx^xx^xx@xx[xx#xx=xx!xx~xx+xx!xx^xx/F:C5#0#0-4/4$100$1+60%24;xx/G:xx_xx/H:xx_xx/I:8_8/J:2~2@1\n";
+
+    #[test]
+    fn full_context_round_trips() {
+        let labels = parse_full_context(FULL_CONTEXT).expect("must parse");
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].start_time_ns, 0);
+        assert_eq!(labels[0].end_time_ns, 500_000_000);
+        assert_eq!(labels[0].label.phoneme.phoneme_id_current, "pau");
+        assert_eq!(format_full_context(&labels), FULL_CONTEXT);
+        assert_eq!(
+            parse_full_context(&format_full_context(&labels)).expect("must parse"),
+            labels
+        );
+    }
+
+    #[test]
+    fn mono_round_trips_and_skips_blank_lines() {
+        let content = "0 1500000 pau\n\n1500000  2000000\tk\r\n2000000 4000000 a\n";
+        let labels = parse_mono(content).expect("must parse");
+        assert_eq!(
+            labels
+                .iter()
+                .map(|l| (l.start_time_ns, l.end_time_ns, l.phoneme.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (0, 150_000_000, "pau"),
+                (150_000_000, 200_000_000, "k"),
+                (200_000_000, 400_000_000, "a"),
+            ]
+        );
+        assert_eq!(
+            format_mono(&labels),
+            "0 1500000 pau\n1500000 2000000 k\n2000000 4000000 a\n"
+        );
+    }
+
+    #[test]
+    fn errors_report_line_numbers() {
+        let error = |content: &str| parse_mono(content).expect_err("must fail").to_string();
+        assert_eq!(
+            error("0 10 a\n\nten 20 b\n"),
+            "line 3: invalid start time \"ten\": invalid digit found in string"
+        );
+        assert_eq!(
+            error("0 10 a\n20 10 b\n"),
+            "line 2: end time 10 is before start time 20"
+        );
+        assert_eq!(error("0 10\n"), "line 1: missing label");
+        assert_eq!(
+            error("0 10 a\n10 184467440737095517 b\n"),
+            "line 2: end time 184467440737095517 is too large"
+        );
+        assert_eq!(
+            error("0 10 a b\n"),
+            "line 1: phoneme \"a b\" contains whitespace"
+        );
+        assert!(parse_full_context("0 10 pau\n")
+            .expect_err("must fail")
+            .to_string()
+            .starts_with("line 1: [root]"));
+        assert_eq!(
+            parse_full_context(&FULL_CONTEXT.replace("/F:C5#", "/F:H5#"))
+                .expect_err("must fail")
+                .to_string(),
+            "line 1: invalid label fields:\n[F] absolute_pitch: expected a note name like C4, got \"H5\""
+        );
+    }
+
+    #[test]
+    fn saves_and_loads_files() {
+        let directory = tempfile::tempdir().expect("must create");
+        let path = directory.path().join("mono.lab");
+        let labels = parse_mono("0 100 pau\n100 300 a\n").expect("must parse");
+        save_mono(&path, &labels).expect("must save");
+        assert_eq!(
+            format_mono(&load_mono(&path).expect("must load")),
+            "0 100 pau\n100 300 a\n"
+        );
+        assert!(load_mono(&directory.path().join("missing.lab")).is_err());
+    }
+}
@@ -1,10 +1,18 @@
 #![allow(clippy::missing_safety_doc)]
 mod config;
+mod dictionary;
 mod engine;
+mod kana;
+mod label_file;
 mod neutrino_label;
 mod neutrino_score;
+mod phoneme_inventory;
+mod phonemizer;
+mod properties;
 mod speaker;
 mod synthesizer;
+mod timing;
+mod voice_switch;
 
 static ENGINE_POINTERS: std::sync::LazyLock<std::sync::Mutex<std::collections::HashSet<usize>>> =
     std::sync::LazyLock::new(|| std::sync::Mutex::new(std::collections::HashSet::new()));
@@ -26,6 +34,8 @@ fn create_c_string(s: &str) -> *mut std::ffi::c_char {
 
 pub struct CEngine {
     engine: std::sync::Mutex<engine::Engine>,
+    // NOTE: 合成中もengineのロックを待たずに使えるよう、音源と歌詞まわりは別に持つ
+    library: std::sync::Arc<std::sync::Mutex<engine::VoiceLibrary>>,
 }
 
 pub struct CancelToken {
@@ -100,6 +110,7 @@ pub unsafe extern "C" fn neutrino_tau_create_engine(
     };
 
     let ptr = Box::into_raw(Box::new(CEngine {
+        library: engine.library(),
         engine: std::sync::Mutex::new(engine),
     }));
     {
@@ -109,9 +120,9 @@ pub unsafe extern "C" fn neutrino_tau_create_engine(
     ptr
 }
 
-#[no_mangle]
-pub unsafe extern "C" fn neutrino_tau_load_voice_sources_json(
+unsafe fn voice_sources_json(
     engine: *mut CEngine,
+    rescan: bool,
     err: *mut *mut std::ffi::c_char,
 ) -> *mut std::ffi::c_char {
     if engine.is_null() {
@@ -123,31 +134,27 @@ pub unsafe extern "C" fn neutrino_tau_load_voice_sources_json(
     }
 
     let engine = unsafe { &*engine };
-    let engine = match engine.engine.lock() {
+    let mut library = match engine.library.lock() {
         Ok(guard) => guard,
         Err(e) => {
             if !err.is_null() {
-                let err_msg = create_c_string(&format!("Failed to acquire engine lock: {}", e));
+                let err_msg =
+                    create_c_string(&format!("Failed to acquire voice library lock: {}", e));
                 *err = err_msg;
             }
             return std::ptr::null_mut();
         }
     };
-    match engine.load_voices() {
-        Ok(voices) => match serde_json::to_string(&voices) {
-            Ok(json) => create_c_string(&json),
-            Err(e) => {
-                if !err.is_null() {
-                    let err_msg =
-                        create_c_string(&format!("Failed to serialize voice sources: {}", e));
-                    *err = err_msg;
-                }
-                std::ptr::null_mut()
-            }
-        },
+    let voices = if rescan {
+        library.rescan_voices()
+    } else {
+        library.load_voices()
+    };
+    match serde_json::to_string(voices) {
+        Ok(json) => create_c_string(&json),
         Err(e) => {
             if !err.is_null() {
-                let err_msg = create_c_string(&format!("Failed to load voice sources: {}", e));
+                let err_msg = create_c_string(&format!("Failed to serialize voice sources: {}", e));
                 *err = err_msg;
             }
             std::ptr::null_mut()
@@ -155,6 +162,22 @@ pub unsafe extern "C" fn neutrino_tau_load_voice_sources_json(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn neutrino_tau_load_voice_sources_json(
+    engine: *mut CEngine,
+    err: *mut *mut std::ffi::c_char,
+) -> *mut std::ffi::c_char {
+    unsafe { voice_sources_json(engine, false, err) }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn neutrino_tau_rescan_voice_sources_json(
+    engine: *mut CEngine,
+    err: *mut *mut std::ffi::c_char,
+) -> *mut std::ffi::c_char {
+    unsafe { voice_sources_json(engine, true, err) }
+}
+
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn neutrino_tau_destroy_engine(engine: *mut CEngine) {
@@ -253,6 +276,177 @@ pub unsafe extern "C" fn neutrino_tau_synthesize(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn neutrino_tau_preview_phonemes(
+    engine: *mut CEngine,
+    voice_id: *const std::ffi::c_char,
+    lyrics_json: *const std::ffi::c_char,
+    err: *mut *mut std::ffi::c_char,
+) -> *mut std::ffi::c_char {
+    if engine.is_null() {
+        if !err.is_null() {
+            let err_msg = create_c_string("Engine is null");
+            unsafe {
+                *err = err_msg;
+            }
+        }
+        return std::ptr::null_mut();
+    }
+
+    if voice_id.is_null() || lyrics_json.is_null() {
+        if !err.is_null() {
+            let err_msg = create_c_string("Voice id or lyrics is null");
+            unsafe {
+                *err = err_msg;
+            }
+        }
+        return std::ptr::null_mut();
+    }
+
+    let (voice_id, lyrics_json) = unsafe {
+        match (
+            std::ffi::CStr::from_ptr(voice_id).to_str(),
+            std::ffi::CStr::from_ptr(lyrics_json).to_str(),
+        ) {
+            (Ok(voice_id), Ok(lyrics_json)) => (voice_id, lyrics_json),
+            _ => {
+                if !err.is_null() {
+                    let err_msg = create_c_string("Voice id or lyrics is not valid UTF-8");
+                    *err = err_msg;
+                }
+                return std::ptr::null_mut();
+            }
+        }
+    };
+
+    let engine = unsafe { &*engine };
+    let mut library = match engine.library.lock() {
+        Ok(guard) => guard,
+        Err(e) => {
+            if !err.is_null() {
+                let err_msg =
+                    create_c_string(&format!("Failed to acquire voice library lock: {}", e));
+                unsafe {
+                    *err = err_msg;
+                }
+            }
+            return std::ptr::null_mut();
+        }
+    };
+
+    let result = serde_json::from_str::<Vec<String>>(lyrics_json)
+        .map_err(|e| anyhow::anyhow!("Failed to parse lyrics: {}", e))
+        .and_then(|lyrics| library.preview_phonemes(voice_id, &lyrics))
+        .and_then(|previews| {
+            serde_json::to_string(&previews)
+                .map_err(|e| anyhow::anyhow!("Failed to serialize phoneme previews: {}", e))
+        });
+    match result {
+        Ok(json) => create_c_string(&json),
+        Err(e) => {
+            if !err.is_null() {
+                let err_msg = create_c_string(&e.to_string());
+                unsafe {
+                    *err = err_msg;
+                }
+            }
+            std::ptr::null_mut()
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn neutrino_tau_voice_property_schema_json(
+    engine: *mut CEngine,
+    voice_id: *const std::ffi::c_char,
+    err: *mut *mut std::ffi::c_char,
+) -> *mut std::ffi::c_char {
+    if engine.is_null() {
+        if !err.is_null() {
+            let err_msg = create_c_string("Engine is null");
+            unsafe {
+                *err = err_msg;
+            }
+        }
+        return std::ptr::null_mut();
+    }
+
+    if voice_id.is_null() {
+        if !err.is_null() {
+            let err_msg = create_c_string("Voice id is null");
+            unsafe {
+                *err = err_msg;
+            }
+        }
+        return std::ptr::null_mut();
+    }
+
+    let voice_id = unsafe {
+        match std::ffi::CStr::from_ptr(voice_id).to_str() {
+            Ok(s) => s,
+            Err(_) => {
+                if !err.is_null() {
+                    let err_msg = create_c_string("Voice id is not valid UTF-8");
+                    *err = err_msg;
+                }
+                return std::ptr::null_mut();
+            }
+        }
+    };
+
+    let engine = unsafe { &*engine };
+    let mut library = match engine.library.lock() {
+        Ok(guard) => guard,
+        Err(e) => {
+            if !err.is_null() {
+                let err_msg =
+                    create_c_string(&format!("Failed to acquire voice library lock: {}", e));
+                unsafe {
+                    *err = err_msg;
+                }
+            }
+            return std::ptr::null_mut();
+        }
+    };
+
+    let result = library.property_schema(voice_id).and_then(|schema| {
+        serde_json::to_string(&schema)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize property schema: {}", e))
+    });
+    match result {
+        Ok(json) => create_c_string(&json),
+        Err(e) => {
+            if !err.is_null() {
+                let err_msg = create_c_string(&e.to_string());
+                unsafe {
+                    *err = err_msg;
+                }
+            }
+            std::ptr::null_mut()
+        }
+    }
+}
+
+// NOTE: 音源の情報を読めないときにTuneLab側で使う、音源によらないプロパティの定義
+#[no_mangle]
+pub unsafe extern "C" fn neutrino_tau_default_property_schema_json(
+    err: *mut *mut std::ffi::c_char,
+) -> *mut std::ffi::c_char {
+    match serde_json::to_string(&properties::PropertySchema::default()) {
+        Ok(json) => create_c_string(&json),
+        Err(e) => {
+            if !err.is_null() {
+                let err_msg =
+                    create_c_string(&format!("Failed to serialize property schema: {}", e));
+                unsafe {
+                    *err = err_msg;
+                }
+            }
+            std::ptr::null_mut()
+        }
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn neutrino_tau_free_c_string(cstr: *mut std::ffi::c_char) {
     if !cstr.is_null() {
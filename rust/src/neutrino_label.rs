@@ -3,6 +3,97 @@ use lazy_regex::{regex, Regex};
 #[derive(Clone, PartialEq, Eq, Default)]
 pub struct LabelValue(String);
 
+const XX: &str = "xx";
+
+// NOTE: 型付きの読み書き。xxはNoneになり、読めない値もNoneになる（validateで検出する）
+impl LabelValue {
+    pub fn as_option(&self) -> Option<&str> {
+        if self.0 == XX {
+            None
+        } else {
+            Some(self.0.as_str())
+        }
+    }
+
+    fn from_option(value: Option<impl std::fmt::Display>) -> Self {
+        value.map_or_else(|| XX.into(), |value| value.to_string().into())
+    }
+
+    pub fn as_count(&self) -> Option<usize> {
+        self.as_option()?.parse().ok()
+    }
+
+    pub fn set_count(&mut self, value: Option<usize>) {
+        *self = Self::from_option(value);
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        self.as_option()?.parse().ok()
+    }
+
+    pub fn set_integer(&mut self, value: Option<i64>) {
+        *self = Self::from_option(value);
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        self.as_option()?
+            .parse()
+            .ok()
+            .filter(|n: &f64| n.is_finite())
+    }
+
+    pub fn set_number(&mut self, value: Option<f64>) {
+        *self = Self::from_option(value);
+    }
+
+    // NOTE: C4 = 60
+    pub fn as_pitch(&self) -> Option<u8> {
+        crate::neutrino_score::note_name_to_midi(self.as_option()?)
+    }
+
+    pub fn set_pitch(&mut self, value: Option<u8>) {
+        *self = Self::from_option(value.map(crate::neutrino_score::midi_to_note_name));
+    }
+
+    // NOTE: 拍子は「分子/分母」
+    pub fn as_beat(&self) -> Option<(u8, u8)> {
+        let (numerator, denominator) = self.as_option()?.split_once('/')?;
+        let numerator = numerator.parse::<u8>().ok().filter(|n| *n > 0)?;
+        let denominator = denominator.parse::<u8>().ok().filter(|n| *n > 0)?;
+        Some((numerator, denominator))
+    }
+
+    pub fn set_beat(&mut self, value: Option<(u8, u8)>) {
+        *self = Self::from_option(
+            value.map(|(numerator, denominator)| format!("{numerator}/{denominator}")),
+        );
+    }
+
+    // NOTE: 音程差は上ならp、下ならmをつけた半音数
+    pub fn as_pitch_difference(&self) -> Option<i32> {
+        let value = self.as_option()?;
+        let (sign, digits) = match value.split_at_checked(1)? {
+            ("p", digits) => (1, digits),
+            ("m", digits) => (-1, digits),
+            _ => return None,
+        };
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some(sign * digits.parse::<i32>().ok()?)
+    }
+
+    pub fn set_pitch_difference(&mut self, value: Option<i32>) {
+        *self = Self::from_option(value.map(|difference| {
+            if difference >= 0 {
+                format!("p{difference}")
+            } else {
+                format!("m{}", -difference)
+            }
+        }));
+    }
+}
+
 impl std::fmt::Debug for LabelValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.0)
@@ -224,6 +315,226 @@ impl std::fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Count,
+    Integer,
+    Number,
+    Pitch,
+    Beat,
+    PitchDifference,
+}
+
+impl FieldKind {
+    fn accepts(self, value: &LabelValue) -> bool {
+        value.as_option().is_none()
+            || match self {
+                FieldKind::Count => value.as_count().is_some(),
+                FieldKind::Integer => value.as_integer().is_some(),
+                FieldKind::Number => value.as_number().is_some(),
+                FieldKind::Pitch => value.as_pitch().is_some(),
+                FieldKind::Beat => value.as_beat().is_some(),
+                FieldKind::PitchDifference => value.as_pitch_difference().is_some(),
+            }
+    }
+
+    fn expected(self) -> &'static str {
+        match self {
+            FieldKind::Count => "a non-negative integer",
+            FieldKind::Integer => "an integer",
+            FieldKind::Number => "a number",
+            FieldKind::Pitch => "a note name like C4",
+            FieldKind::Beat => "a beat like 4/4",
+            FieldKind::PitchDifference => "a pitch difference like p2 or m2",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub section: &'static str,
+    pub name: &'static str,
+    pub value: LabelValue,
+    pub expected: &'static str,
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {}: expected {}, got {:?}",
+            self.section, self.name, self.expected, self.value
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationError {
+    pub errors: Vec<FieldError>,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid label fields:")?;
+        for error in &self.errors {
+            write!(f, "\n{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+macro_rules! typed_fields {
+    ($section:literal, $context:expr, { $($name:ident: $kind:ident),* $(,)? }) => {
+        vec![$(($section, stringify!($name), &$context.$name, FieldKind::$kind)),*]
+    };
+}
+
+impl Label {
+    // NOTE: 文字列のままの項目（音素、言語、フラグ、強弱記号など）は検証しない
+    fn typed_fields(&self) -> Vec<(&'static str, &'static str, &LabelValue, FieldKind)> {
+        [
+            typed_fields!("P", self.phoneme, {
+                syllable_phoneme_position_forward: Count,
+                syllable_phoneme_position_backward: Count,
+                distance_from_prev_vowel: Count,
+                distance_to_next_vowel: Count,
+            }),
+            typed_fields!("A", self.prev_syllable, {
+                phoneme_count: Count,
+                note_position_forward: Count,
+                note_position_backward: Count,
+            }),
+            typed_fields!("B", self.curr_syllable, {
+                phoneme_count: Count,
+                note_position_forward: Count,
+                note_position_backward: Count,
+            }),
+            typed_fields!("C", self.next_syllable, {
+                phoneme_count: Count,
+                note_position_forward: Count,
+                note_position_backward: Count,
+            }),
+            typed_fields!("D", self.prev_note, {
+                absolute_pitch: Pitch,
+                relative_pitch: Count,
+                key_signature: Integer,
+                beat: Beat,
+                tempo: Number,
+                length_syllable: Count,
+                length_centisecond: Integer,
+                length_triplet_32nd: Integer,
+            }),
+            typed_fields!("E", self.curr_note, {
+                absolute_pitch: Pitch,
+                relative_pitch: Count,
+                key_signature: Integer,
+                beat: Beat,
+                tempo: Number,
+                length_syllable: Count,
+                length_centisecond: Integer,
+                length_triplet_32nd: Integer,
+                measure_note_position_note_forward: Count,
+                measure_note_position_note_backward: Count,
+                measure_note_position_centisecond_forward: Integer,
+                measure_note_position_centisecond_backward: Integer,
+                measure_note_position_triplet_32nd_forward: Integer,
+                measure_note_position_triplet_32nd_backward: Integer,
+                measure_note_position_percent_forward: Integer,
+                measure_note_position_percent_backward: Integer,
+                phrase_note_position_note_forward: Count,
+                phrase_note_position_note_backward: Count,
+                phrase_note_position_centisecond_forward: Integer,
+                phrase_note_position_centisecond_backward: Integer,
+                phrase_note_position_triplet_32nd_forward: Integer,
+                phrase_note_position_triplet_32nd_backward: Integer,
+                phrase_note_position_percent_forward: Integer,
+                phrase_note_position_percent_backward: Integer,
+                slur_with_previous: Count,
+                slur_with_next: Count,
+                distance_to_next_accent_note: Count,
+                distance_to_previous_accent_note: Count,
+                distance_to_next_accent_centisecond: Count,
+                distance_to_previous_accent_centisecond: Count,
+                distance_to_next_accent_triplet_32nd: Count,
+                distance_to_previous_accent_triplet_32nd: Count,
+                distance_to_next_staccato_note: Count,
+                distance_to_previous_staccato_note: Count,
+                distance_to_next_staccato_centisecond: Count,
+                distance_to_previous_staccato_centisecond: Count,
+                distance_to_next_staccato_triplet_32nd: Count,
+                distance_to_previous_staccato_triplet_32nd: Count,
+                crescendo_position_note_forward: Count,
+                crescendo_position_note_backward: Count,
+                crescendo_position_second_forward: Integer,
+                crescendo_position_second_backward: Integer,
+                crescendo_position_triplet_32nd_forward: Integer,
+                crescendo_position_triplet_32nd_backward: Integer,
+                crescendo_position_percent_forward: Integer,
+                crescendo_position_percent_backward: Integer,
+                decrescendo_position_note_forward: Count,
+                decrescendo_position_note_backward: Count,
+                decrescendo_position_second_forward: Integer,
+                decrescendo_position_second_backward: Integer,
+                decrescendo_position_triplet_32nd_forward: Integer,
+                decrescendo_position_triplet_32nd_backward: Integer,
+                decrescendo_position_percent_forward: Integer,
+                decrescendo_position_percent_backward: Integer,
+                pitch_difference_from_previous_note: PitchDifference,
+                pitch_difference_to_next_note: PitchDifference,
+            }),
+            typed_fields!("F", self.next_note, {
+                absolute_pitch: Pitch,
+                relative_pitch: Count,
+                key_signature: Integer,
+                beat: Beat,
+                tempo: Number,
+                length_syllable: Count,
+                length_centisecond: Integer,
+                length_triplet_32nd: Integer,
+            }),
+            typed_fields!("G", self.prev_phrase, {
+                syllable_count: Count,
+                phoneme_count: Count,
+            }),
+            typed_fields!("H", self.curr_phrase, {
+                syllable_count: Count,
+                phoneme_count: Count,
+            }),
+            typed_fields!("I", self.next_phrase, {
+                syllable_count: Count,
+                phoneme_count: Count,
+            }),
+            typed_fields!("J", self.song, {
+                syllable_per_measure: Count,
+                phoneme_per_measure: Count,
+                phrase_count: Count,
+            }),
+        ]
+        .concat()
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let errors = self
+            .typed_fields()
+            .into_iter()
+            .filter(|(_, _, value, kind)| !kind.accepts(value))
+            .map(|(section, name, value, kind)| FieldError {
+                section,
+                name,
+                value: value.clone(),
+                expected: kind.expected(),
+            })
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { errors })
+        }
+    }
+}
+
 impl std::fmt::Display for Label {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -686,7 +997,75 @@ mod tests {
             .collect();
         assert_eq!(lines.len(), parsed_labels.len());
         assert_eq!(lines, reconstructed_lines.as_slice());
+        assert!(parsed_labels.iter().all(|label| label.validate().is_ok()));
 
         insta::assert_debug_snapshot!(parsed_labels);
     }
+
+    #[test]
+    fn typed_accessors() {
+        let line = "p@xx^xx-pau+r=a_xx%xx^00_00~00-1!1[xx$xx]xx/A:xx-xx-xx@xx~xx/B:1_1_1@xx|xx/C:2+1+1@JPN&0/D:xx!xx#xx$xx%xx|xx&xx;xx-xx/E:xx]xx^0=4/4~100!1@240#96+xx]1$1|0[24&0]96=0^100~xx#xx_xx;xx$xx&xx%xx[xx|0]0-n^xx+xx~xx=xx@xx$xx!xx%xx#xx|xx|xx-xx&xx&xx+xx[xx;xx]xx;xx~xx~xx^xx^xx@xx[xx#xx=xx!xx~m2+xx!xx^xx/F:C5#0#0-4/4$100$1+60%24;xx/G:xx_xx/H:xx_xx/I:8_8/J:2~2@1";
+        let mut label = parse_label_line(line).expect("should parse");
+        assert_eq!(label.next_note.absolute_pitch.as_pitch(), Some(72));
+        assert_eq!(label.next_note.beat.as_beat(), Some((4, 4)));
+        assert_eq!(label.next_note.tempo.as_number(), Some(100.0));
+        assert_eq!(label.next_note.key_signature.as_integer(), Some(0));
+        assert_eq!(label.next_phrase.syllable_count.as_count(), Some(8));
+        assert_eq!(label.curr_note.absolute_pitch.as_pitch(), None);
+        assert_eq!(
+            label
+                .curr_note
+                .pitch_difference_from_previous_note
+                .as_pitch_difference(),
+            Some(-2)
+        );
+        assert_eq!(label.validate().map_err(|e| e.to_string()), Ok(()));
+
+        label.curr_note.absolute_pitch.set_pitch(Some(61));
+        label.curr_note.beat.set_beat(Some((6, 8)));
+        label
+            .curr_note
+            .pitch_difference_to_next_note
+            .set_pitch_difference(Some(3));
+        label.curr_note.key_signature.set_integer(Some(-3));
+        label.next_note.absolute_pitch.set_pitch(None);
+        assert_eq!(label.curr_note.absolute_pitch, "Db4");
+        assert_eq!(label.curr_note.beat, "6/8");
+        assert_eq!(label.curr_note.pitch_difference_to_next_note, "p3");
+        assert_eq!(label.curr_note.key_signature.as_integer(), Some(-3));
+        assert_eq!(label.next_note.absolute_pitch, "xx");
+        assert_eq!(
+            parse_label_line(&label.to_string()).expect("should parse"),
+            label
+        );
+    }
+
+    #[test]
+    fn validate_reports_malformed_fields() {
+        let line = "p@xx^xx-pau+r=a_xx%xx^00_00~00-1!1[xx$xx]xx/A:xx-xx-xx@xx~xx/B:1_1_1@xx|xx/C:2+1+1@JPN&0/D:xx!xx#xx$xx%xx|xx&xx;xx-xx/E:xx]xx^0=4/4~100!1@240#96+xx]1$1|0[24&0]96=0^100~xx#xx_xx;xx$xx&xx%xx[xx|0]0-n^xx+xx~xx=xx@xx$xx!xx%xx#xx|xx|xx-xx&xx&xx+xx[xx;xx]xx;xx~xx~xx^xx^xx@xx[xx#xx=xx!xx~xx+xx!xx^xx/F:C5#0#0-4/4$100$1+60%24;xx/G:xx_xx/H:xx_xx/I:8_8/J:2~2@1";
+        let mut label = parse_label_line(line).expect("should parse");
+        label.curr_note.absolute_pitch = "H4".into();
+        label.curr_note.beat = "4/0".into();
+        label.curr_note.pitch_difference_to_next_note = "2".into();
+        label.song.phrase_count = "-1".into();
+        label.phoneme.phoneme_id_current = "whatever".into();
+        let error = label.validate().expect_err("must fail");
+        assert_eq!(
+            error
+                .errors
+                .iter()
+                .map(|e| (e.section, e.name))
+                .collect::<Vec<_>>(),
+            [
+                ("E", "absolute_pitch"),
+                ("E", "beat"),
+                ("E", "pitch_difference_to_next_note"),
+                ("J", "phrase_count"),
+            ]
+        );
+        assert_eq!(
+            error.errors[0].to_string(),
+            "[E] absolute_pitch: expected a note name like C4, got \"H4\""
+        );
+    }
 }
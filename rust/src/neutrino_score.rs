@@ -2,6 +2,7 @@ use crate::neutrino_label::{parse_label_line, Label, LabelValue};
 
 const TEMPLATE_LABEL_LINE: &str = "p@xx^xx-pau+r=a_xx%xx^00_00~00-1!1[xx$xx]xx/A:xx-xx-xx@xx~xx/B:1_1_1@xx|xx/C:2+1+1@JPN&0/D:xx!xx#xx$xx%xx|xx&xx;xx-xx/E:xx]xx^0=4/4~100!1@240#96+xx]1$1|0[24&0]96=0^100~xx#xx_xx;xx$xx&xx%xx[xx|0]0-n^xx+xx~xx=xx@xx$xx!xx%xx#xx|xx|xx-xx&xx&xx+xx[xx;xx]xx;xx~xx~xx^xx^xx@xx[xx#xx=xx!xx~xx+xx!xx^xx/F:C5#0#0-4/4$100$1+60%24;xx/G:xx_xx/H:xx_xx/I:8_8/J:2~2@1";
 const XX: &str = "xx";
+const REST_PHONEMES: &[&str] = &["pau", "br", "sil"];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct NoteLength(i32);
@@ -66,32 +67,87 @@ pub struct Note {
     pub pitch: Option<u8>,
     pub start_time_ns: u64,
     pub length: NoteLength,
+    // NOTE: 音価は32分3連符に丸めたものなので、TuneLabのノートの終了時刻はこちらで持つ。Noneなら音価から求める
+    pub end_time_ns: Option<u64>,
     pub phonemes: Vec<String>,
     pub language: Option<String>,
     pub language_dependent_context: Option<String>,
+    // NOTE: 前のノートとスラーでつながっているか（メリスマ）
+    pub slur: bool,
+    pub marks: NoteMarks,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dynamic {
+    Pianissimo,
+    Piano,
+    MezzoPiano,
+    MezzoForte,
+    Forte,
+    Fortissimo,
+}
+
+impl Dynamic {
+    // NOTE: ラベルにはSinsy（musicXMLtoLabelの元）の表記で書く。強弱記号がなければ「n」
+    pub fn label_symbol(self) -> &'static str {
+        match self {
+            Dynamic::Pianissimo => "p2",
+            Dynamic::Piano => "p1",
+            Dynamic::MezzoPiano => "mp",
+            Dynamic::MezzoForte => "mf",
+            Dynamic::Forte => "f1",
+            Dynamic::Fortissimo => "f2",
+        }
+    }
+}
+
+impl std::str::FromStr for Dynamic {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "pp" => Dynamic::Pianissimo,
+            "p" => Dynamic::Piano,
+            "mp" => Dynamic::MezzoPiano,
+            "mf" => Dynamic::MezzoForte,
+            "f" => Dynamic::Forte,
+            "ff" => Dynamic::Fortissimo,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoteMarks {
+    pub dynamic: Option<Dynamic>,
+    pub accent: bool,
+    pub staccato: bool,
+}
+
+impl Note {
+    pub fn is_rest(&self) -> bool {
+        self.pitch.is_none()
+            || self
+                .phonemes
+                .iter()
+                .all(|p| REST_PHONEMES.contains(&p.as_str()))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ComposeOptions {
-    pub tempo: f64,
-    pub beat: String,
-    pub key_signature: String,
     pub phrase_count: usize,
 }
 
 impl Default for ComposeOptions {
     fn default() -> Self {
-        Self {
-            tempo: 120.0,
-            beat: "4/4".to_string(),
-            key_signature: "0".to_string(),
-            phrase_count: 1,
-        }
+        Self { phrase_count: 1 }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TimeSignature {
+    pub start_time_ns: u64,
     pub numerator: u8,
     pub denominator: u8,
 }
@@ -99,29 +155,266 @@ pub struct TimeSignature {
 impl Default for TimeSignature {
     fn default() -> Self {
         Self {
+            start_time_ns: 0,
             numerator: 4,
             denominator: 4,
         }
     }
 }
 
+impl TimeSignature {
+    pub fn beat(&self) -> (u8, u8) {
+        (self.numerator, self.denominator)
+    }
+
+    pub fn measure_length_triplet_32nd(&self) -> i64 {
+        (self.numerator as i64 * 96 / self.denominator.max(1) as i64).max(1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tempo {
+    pub start_time_ns: u64,
+    pub tempo: f64,
+}
+
+impl Default for Tempo {
+    fn default() -> Self {
+        Self {
+            start_time_ns: 0,
+            tempo: 120.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Score {
     pub notes: Vec<Note>,
-    pub tempo: f64,
+    pub tempos: Vec<Tempo>,
     pub time_signatures: Vec<TimeSignature>,
+    pub key_signatures: Vec<KeySignature>,
+    pub hairpins: Vec<Hairpin>,
 }
 
 impl Default for Score {
     fn default() -> Self {
         Self {
             notes: Vec::new(),
-            tempo: 120.0,
+            tempos: vec![Tempo::default()],
             time_signatures: vec![TimeSignature::default()],
+            key_signatures: vec![KeySignature::default()],
+            hairpins: Vec::new(),
         }
     }
 }
 
+// NOTE: 調号は五度圏上の位置（シャープなら正、フラットなら負）で表す
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeySignature {
+    pub start_time_ns: u64,
+    pub fifths: i8,
+}
+
+impl KeySignature {
+    pub fn tonic(&self) -> u8 {
+        (i32::from(self.fifths) * 7).rem_euclid(12) as u8
+    }
+
+    fn from_tonic(start_time_ns: u64, tonic: u8) -> Self {
+        let fifths = (i32::from(tonic) * 7).rem_euclid(12);
+        Self {
+            start_time_ns,
+            fifths: if fifths > 6 { fifths - 12 } else { fifths } as i8,
+        }
+    }
+}
+
+// Krumhansl-Kessler key profiles
+const MAJOR_KEY_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_KEY_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+// NOTE: 音価で重みづけした音高クラスの分布と長調・短調のプロファイルの相関が最大になる調を選ぶ。
+//       短調は平行長調の調号にする
+pub fn estimate_key_signature(notes: &[Note]) -> KeySignature {
+    let mut histogram = [0.0; 12];
+    for note in notes.iter().filter(|n| !n.is_rest()) {
+        if let Some(pitch) = note.pitch {
+            histogram[usize::from(pitch % 12)] += f64::from(i32::from(note.length).max(1));
+        }
+    }
+    if histogram.iter().all(|&weight| weight == 0.0) {
+        return KeySignature::default();
+    }
+
+    let correlation = |profile: &[f64; 12], tonic: usize| {
+        let rotated = (0..12)
+            .map(|i| profile[(i + 12 - tonic) % 12])
+            .collect::<Vec<_>>();
+        let mean_x = histogram.iter().sum::<f64>() / 12.0;
+        let mean_y = rotated.iter().sum::<f64>() / 12.0;
+        let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+        for (x, y) in histogram.iter().zip(&rotated) {
+            covariance += (x - mean_x) * (y - mean_y);
+            variance_x += (x - mean_x).powi(2);
+            variance_y += (y - mean_y).powi(2);
+        }
+        covariance / (variance_x * variance_y).sqrt().max(f64::EPSILON)
+    };
+    let (tonic, _) = (0..12)
+        .map(|tonic| (tonic, correlation(&MAJOR_KEY_PROFILE, tonic)))
+        .chain((0..12).map(|tonic| ((tonic + 3) % 12, correlation(&MINOR_KEY_PROFILE, tonic))))
+        .fold((0, f64::NEG_INFINITY), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        });
+    KeySignature::from_tonic(0, tonic as u8)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HairpinKind {
+    Crescendo,
+    Decrescendo,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hairpin {
+    pub kind: HairpinKind,
+    pub start_time_ns: u64,
+    pub end_time_ns: u64,
+}
+
+impl Score {
+    pub fn tempo_at(&self, time_ns: u64) -> f64 {
+        let tempos = self
+            .tempos
+            .iter()
+            .filter(|t| t.tempo.is_finite() && t.tempo > 0.0);
+        tempos
+            .clone()
+            .filter(|t| t.start_time_ns <= time_ns)
+            .max_by_key(|t| t.start_time_ns)
+            .or_else(|| tempos.min_by_key(|t| t.start_time_ns))
+            .map_or(Tempo::default().tempo, |t| t.tempo)
+    }
+
+    pub fn time_signature_at(&self, time_ns: u64) -> TimeSignature {
+        self.time_signatures
+            .iter()
+            .filter(|t| t.start_time_ns <= time_ns)
+            .max_by_key(|t| t.start_time_ns)
+            .or_else(|| self.time_signatures.iter().min_by_key(|t| t.start_time_ns))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn key_signature_at(&self, time_ns: u64) -> KeySignature {
+        self.key_signatures
+            .iter()
+            .filter(|k| k.start_time_ns <= time_ns)
+            .max_by_key(|k| k.start_time_ns)
+            .or_else(|| self.key_signatures.iter().min_by_key(|k| k.start_time_ns))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn next_tempo_change_after(&self, time_ns: u64) -> Option<u64> {
+        self.tempos
+            .iter()
+            .map(|t| t.start_time_ns)
+            .filter(|&start| start > time_ns)
+            .min()
+    }
+
+    // NOTE: テンポ変化をまたいで積分する（単位は32分3連符）
+    pub fn triplet_32nd_between(&self, start_ns: u64, end_ns: u64) -> f64 {
+        let mut ticks = 0.0;
+        let mut current_ns = start_ns;
+        while current_ns < end_ns {
+            let segment_end_ns = self
+                .next_tempo_change_after(current_ns)
+                .map_or(end_ns, |change| change.min(end_ns));
+            ticks +=
+                (segment_end_ns - current_ns) as f64 * self.tempo_at(current_ns) / 2_500_000_000.0;
+            current_ns = segment_end_ns;
+        }
+        ticks
+    }
+
+    pub fn advance_triplet_32nd(&self, start_ns: u64, length_triplet_32nd: i32) -> u64 {
+        let mut remaining = length_triplet_32nd.max(0) as f64;
+        let mut current_ns = start_ns as f64;
+        while remaining > 0.0 {
+            let tempo = self.tempo_at(current_ns as u64);
+            let segment_ns = remaining * 2_500_000_000.0 / tempo;
+            match self.next_tempo_change_after(current_ns as u64) {
+                Some(change) if (change as f64) < current_ns + segment_ns => {
+                    remaining -= (change as f64 - current_ns) * tempo / 2_500_000_000.0;
+                    current_ns = change as f64;
+                }
+                _ => {
+                    current_ns += segment_ns;
+                    remaining = 0.0;
+                }
+            }
+        }
+        if !current_ns.is_finite() || current_ns >= u64::MAX as f64 {
+            u64::MAX
+        } else {
+            current_ns.round() as u64
+        }
+    }
+
+    // NOTE: 0より前は最初のテンポで外挿する
+    fn triplet_32nd_to_signed_ns(&self, position_triplet_32nd: i64) -> i64 {
+        if position_triplet_32nd >= 0 {
+            self.advance_triplet_32nd(0, position_triplet_32nd.min(i32::MAX as i64) as i32) as i64
+        } else {
+            (position_triplet_32nd as f64 * 2_500_000_000.0 / self.tempo_at(0)).round() as i64
+        }
+    }
+
+    fn ns_to_triplet_32nd(&self, time_ns: u64) -> i64 {
+        self.triplet_32nd_between(0, time_ns).round() as i64
+    }
+
+    // NOTE: 小節の開始位置と終了位置を32分3連符単位で返す
+    fn measure_at(&self, position_triplet_32nd: i64) -> (i64, i64) {
+        let mut time_signatures = self
+            .time_signatures
+            .iter()
+            .map(|t| (self.ns_to_triplet_32nd(t.start_time_ns), t))
+            .collect::<Vec<_>>();
+        time_signatures.sort_by_key(|(position, _)| *position);
+        let default_time_signature = TimeSignature::default();
+        let current = time_signatures
+            .iter()
+            .rev()
+            .find(|(position, _)| *position <= position_triplet_32nd)
+            .or_else(|| time_signatures.first())
+            .copied()
+            .unwrap_or((0, &default_time_signature));
+        let measure_length = current.1.measure_length_triplet_32nd();
+        let measure_start = current.0
+            + (position_triplet_32nd - current.0).div_euclid(measure_length) * measure_length;
+        let measure_end = time_signatures
+            .iter()
+            .map(|(position, _)| *position)
+            .filter(|&position| position > measure_start)
+            .min()
+            .map_or(measure_start + measure_length, |next| {
+                next.min(measure_start + measure_length)
+            });
+        (measure_start, measure_end)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ComposeError {
     TemplateParse(String),
@@ -149,19 +442,9 @@ pub struct TimedLabel {
 }
 
 pub fn compose_labels_from_score(score: &Score) -> Result<Vec<TimedLabel>, ComposeError> {
-    let time_signature = score
-        .time_signatures
-        .first()
-        .cloned()
-        .unwrap_or_else(TimeSignature::default);
+    let phrases = detect_phrases(&score.notes);
     let options = ComposeOptions {
-        tempo: score.tempo,
-        beat: format!(
-            "{}/{}",
-            time_signature.numerator, time_signature.denominator
-        ),
-        key_signature: "0".to_string(),
-        phrase_count: 1,
+        phrase_count: phrases.len(),
     };
     for (i, note) in score.notes.iter().enumerate() {
         if note.phonemes.is_empty() {
@@ -173,14 +456,21 @@ pub fn compose_labels_from_score(score: &Score) -> Result<Vec<TimedLabel>, Compo
         .map_err(|e| ComposeError::TemplateParse(e.to_string()))?;
     let points = flatten_points(&score.notes);
     let mut labels = Vec::with_capacity(points.len());
-    let note_time_ranges_ns = compute_note_time_ranges_ns(&score.notes, options.tempo);
+    let note_time_ranges_ns = compute_score_time_ranges_ns(score);
+    let note_timings = compute_note_timings(score, &note_time_ranges_ns, &phrases);
 
     for point in &points {
         let mut label = template.clone();
         fill_phoneme_context(&mut label, &points, point.index);
         fill_syllable_contexts(&mut label, &score.notes, point.note_index);
-        fill_note_contexts(&mut label, &score.notes, point.note_index, &options);
-        fill_phrase_and_song_contexts(&mut label, &score.notes, &options);
+        fill_note_contexts(&mut label, &score.notes, &note_timings, point.note_index);
+        fill_phrase_and_song_contexts(
+            &mut label,
+            &score.notes,
+            &phrases,
+            point.note_index,
+            &options,
+        );
         let (note_start_ns, note_end_ns) = note_time_ranges_ns[point.note_index];
         // NOTE: Neutrinoは同じノートであればどの音素でも同じ開始・終了時間になる
         labels.push(TimedLabel {
@@ -193,25 +483,234 @@ pub fn compose_labels_from_score(score: &Score) -> Result<Vec<TimedLabel>, Compo
     Ok(labels)
 }
 
-fn compute_note_time_ranges_ns(notes: &[Note], tempo: f64) -> Vec<(u64, u64)> {
-    let mut current_ns: u128 = 0;
-    let mut ranges = Vec::with_capacity(notes.len());
-    for note in notes {
-        let length_triplet_32nd: i32 = note.length.into();
-        let note_duration_ns =
-            length_triplet_32nd_to_nanoseconds(length_triplet_32nd, tempo) as u128;
+pub fn compute_score_time_ranges_ns(score: &Score) -> Vec<(u64, u64)> {
+    compute_note_time_ranges_ns(score)
+}
+
+fn compute_note_time_ranges_ns(score: &Score) -> Vec<(u64, u64)> {
+    let mut current_ns: u64 = 0;
+    let mut ranges = Vec::with_capacity(score.notes.len());
+    for note in &score.notes {
         // Keep legacy sequential behavior when start_time is 0, but honor explicit note start.
-        let start_ns = current_ns.max(note.start_time_ns as u128);
-        let end_ns = start_ns.saturating_add(note_duration_ns);
-        ranges.push((
-            start_ns.min(u64::MAX as u128) as u64,
-            end_ns.min(u64::MAX as u128) as u64,
-        ));
+        let start_ns = current_ns.max(note.start_time_ns);
+        let end_ns = note.end_time_ns.map_or_else(
+            || score.advance_triplet_32nd(start_ns, note.length.into()),
+            |end_ns| end_ns.max(start_ns),
+        );
+        ranges.push((start_ns, end_ns));
         current_ns = end_ns;
     }
     ranges
 }
 
+#[derive(Debug, Clone)]
+struct NoteTiming {
+    start_time_ns: u64,
+    end_time_ns: u64,
+    position_triplet_32nd: i64,
+    tempo: f64,
+    key_signature: KeySignature,
+    beat: (u8, u8),
+    measure: NotePosition,
+    phrase: Option<NotePosition>,
+    crescendo: Option<NotePosition>,
+    decrescendo: Option<NotePosition>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct NotePosition {
+    note_forward: usize,
+    note_backward: usize,
+    decisecond_forward: i64,
+    decisecond_backward: i64,
+    triplet_32nd_forward: i64,
+    triplet_32nd_backward: i64,
+    percent_forward: i64,
+    percent_backward: i64,
+}
+
+// NOTE: 小節やフレーズなど、ノートを含む区間（32分3連符単位とns単位）
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: i64,
+    end: i64,
+    start_ns: i64,
+    end_ns: i64,
+}
+
+impl NotePosition {
+    fn within(
+        span: Span,
+        note_forward: usize,
+        note_count: usize,
+        position: i64,
+        start_ns: u64,
+    ) -> Self {
+        let percent_forward =
+            ((position - span.start) * 100).div_euclid((span.end - span.start).max(1));
+        Self {
+            note_forward,
+            note_backward: note_count - note_forward + 1,
+            decisecond_forward: ns_to_deciseconds(start_ns as i64 - span.start_ns),
+            decisecond_backward: ns_to_deciseconds(span.end_ns - start_ns as i64),
+            triplet_32nd_forward: position - span.start,
+            triplet_32nd_backward: span.end - position,
+            percent_forward,
+            percent_backward: 100 - percent_forward,
+        }
+    }
+}
+
+fn compute_note_timings(
+    score: &Score,
+    note_time_ranges_ns: &[(u64, u64)],
+    phrases: &[std::ops::Range<usize>],
+) -> Vec<NoteTiming> {
+    let measures = note_time_ranges_ns
+        .iter()
+        .map(|&(start_ns, _)| {
+            let position = score.ns_to_triplet_32nd(start_ns);
+            (position, score.measure_at(position))
+        })
+        .collect::<Vec<_>>();
+    // NOTE: 開始位置が松葉の範囲内にある休符以外のノートを対象とする
+    let hairpin_notes = score
+        .hairpins
+        .iter()
+        .filter(|hairpin| hairpin.start_time_ns < hairpin.end_time_ns)
+        .map(|hairpin| {
+            let covered = note_time_ranges_ns
+                .iter()
+                .enumerate()
+                .filter(|&(i, &(start_ns, _))| {
+                    !score.notes[i].is_rest()
+                        && (hairpin.start_time_ns..hairpin.end_time_ns).contains(&start_ns)
+                })
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            (hairpin, covered)
+        })
+        .collect::<Vec<_>>();
+
+    // NOTE: 長いパートでも線形時間で済むよう、小節・フレーズ・松葉内の位置は先に数えておく
+    let mut notes_per_measure = std::collections::HashMap::<i64, usize>::new();
+    for (_, (measure_start, _)) in &measures {
+        *notes_per_measure.entry(*measure_start).or_default() += 1;
+    }
+    let mut seen_per_measure = std::collections::HashMap::<i64, usize>::new();
+    let measure_note_forwards = measures
+        .iter()
+        .map(|(_, (measure_start, _))| {
+            let seen = seen_per_measure.entry(*measure_start).or_default();
+            *seen += 1;
+            *seen
+        })
+        .collect::<Vec<_>>();
+    let mut note_phrases = vec![None; note_time_ranges_ns.len()];
+    for phrase in phrases {
+        for i in phrase.clone() {
+            note_phrases[i].get_or_insert(phrase);
+        }
+    }
+    let mut note_hairpins = vec![(None, None); note_time_ranges_ns.len()];
+    for (hairpin_index, (hairpin, covered)) in hairpin_notes.iter().enumerate() {
+        for (position, &i) in covered.iter().enumerate() {
+            let slot = match hairpin.kind {
+                HairpinKind::Crescendo => &mut note_hairpins[i].0,
+                HairpinKind::Decrescendo => &mut note_hairpins[i].1,
+            };
+            slot.get_or_insert((hairpin_index, position + 1));
+        }
+    }
+
+    note_time_ranges_ns
+        .iter()
+        .zip(&measures)
+        .enumerate()
+        .map(
+            |(i, (&(start_ns, end_ns), &(position, (measure_start, measure_end))))| {
+                let notes_in_measure = notes_per_measure[&measure_start];
+                let note_forward = measure_note_forwards[i];
+                let measure = Span {
+                    start: measure_start,
+                    end: measure_end,
+                    start_ns: score.triplet_32nd_to_signed_ns(measure_start),
+                    end_ns: score.triplet_32nd_to_signed_ns(measure_end),
+                };
+                let phrase = note_phrases[i].map(|p| {
+                    let phrase_start_ns = note_time_ranges_ns[p.start].0;
+                    let phrase_end_ns = note_time_ranges_ns[p.end - 1].1;
+                    let span = Span {
+                        start: score.ns_to_triplet_32nd(phrase_start_ns),
+                        end: score.ns_to_triplet_32nd(phrase_end_ns),
+                        start_ns: phrase_start_ns as i64,
+                        end_ns: phrase_end_ns as i64,
+                    };
+                    NotePosition::within(span, i - p.start + 1, p.len(), position, start_ns)
+                });
+                let hairpin = |slot: Option<(usize, usize)>| {
+                    slot.map(|(hairpin_index, note_forward)| {
+                        let (hairpin, covered) = &hairpin_notes[hairpin_index];
+                        let span = Span {
+                            start: score.ns_to_triplet_32nd(hairpin.start_time_ns),
+                            end: score.ns_to_triplet_32nd(hairpin.end_time_ns),
+                            start_ns: hairpin.start_time_ns as i64,
+                            end_ns: hairpin.end_time_ns as i64,
+                        };
+                        NotePosition::within(span, note_forward, covered.len(), position, start_ns)
+                    })
+                };
+                NoteTiming {
+                    start_time_ns: start_ns,
+                    end_time_ns: end_ns,
+                    position_triplet_32nd: position,
+                    tempo: score.tempo_at(start_ns),
+                    key_signature: score.key_signature_at(start_ns),
+                    beat: score.time_signature_at(start_ns).beat(),
+                    measure: NotePosition::within(
+                        measure,
+                        note_forward,
+                        notes_in_measure,
+                        position,
+                        start_ns,
+                    ),
+                    phrase,
+                    crescendo: hairpin(note_hairpins[i].0),
+                    decrescendo: hairpin(note_hairpins[i].1),
+                }
+            },
+        )
+        .collect()
+}
+
+// NOTE: 休符（pau・br）で区切られたノートの並びをフレーズとする
+fn detect_phrases(notes: &[Note]) -> Vec<std::ops::Range<usize>> {
+    let mut phrases = Vec::new();
+    let mut phrase_start = None;
+    for (i, note) in notes.iter().enumerate() {
+        match (note.is_rest(), phrase_start) {
+            (false, None) => phrase_start = Some(i),
+            (true, Some(start)) => {
+                phrases.push(start..i);
+                phrase_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = phrase_start {
+        phrases.push(start..notes.len());
+    }
+    phrases
+}
+
+fn ns_to_deciseconds(ns: i64) -> i64 {
+    (ns as f64 / 100_000_000.0).round() as i64
+}
+
+fn ns_to_centiseconds(ns: i64) -> i64 {
+    (ns as f64 / 10_000_000.0).round() as i64
+}
+
 #[derive(Debug, Clone)]
 struct Point {
     index: usize,
@@ -253,10 +752,14 @@ fn fill_phoneme_context(label: &mut Label, points: &[Point], index: usize) {
     label.phoneme.phoneme_flag_current = "00".into();
     label.phoneme.phoneme_flag_next = "00".into();
     label.phoneme.phoneme_flag_two_after = "00".into();
-    label.phoneme.syllable_phoneme_position_forward =
-        (current.phoneme_index + 1).to_string().into();
-    label.phoneme.syllable_phoneme_position_backward =
-        (current_count - current.phoneme_index).to_string().into();
+    label
+        .phoneme
+        .syllable_phoneme_position_forward
+        .set_count(Some(current.phoneme_index + 1));
+    label
+        .phoneme
+        .syllable_phoneme_position_backward
+        .set_count(Some(current_count - current.phoneme_index));
     label.phoneme.distance_from_prev_vowel = XX.into();
     label.phoneme.distance_to_next_vowel = XX.into();
     label.phoneme.reserved = XX.into();
@@ -295,8 +798,8 @@ fn fill_syllable_contexts(label: &mut Label, notes: &[Note], note_index: usize)
 fn fill_note_contexts(
     label: &mut Label,
     notes: &[Note],
+    note_timings: &[NoteTiming],
     note_index: usize,
-    options: &ComposeOptions,
 ) {
     fill_note(
         &mut label.prev_note.absolute_pitch,
@@ -309,8 +812,8 @@ fn fill_note_contexts(
         &mut label.prev_note.length_triplet_32nd,
         &mut label.prev_note.reserved,
         notes,
+        note_timings,
         note_index.checked_sub(1),
-        options,
     );
     fill_note(
         &mut label.curr_note.absolute_pitch,
@@ -323,8 +826,8 @@ fn fill_note_contexts(
         &mut label.curr_note.length_triplet_32nd,
         &mut label.curr_note.reserved,
         notes,
+        note_timings,
         Some(note_index),
-        options,
     );
     fill_note(
         &mut label.next_note.absolute_pitch,
@@ -337,84 +840,215 @@ fn fill_note_contexts(
         &mut label.next_note.length_triplet_32nd,
         &mut label.next_note.reserved,
         notes,
+        note_timings,
         note_index.checked_add(1).filter(|i| *i < notes.len()),
-        options,
     );
 
-    label.curr_note.measure_note_position_note_forward = "1".into();
-    label.curr_note.measure_note_position_note_backward = "1".into();
-    label.curr_note.measure_note_position_centisecond_forward = "0".into();
-    label.curr_note.measure_note_position_centisecond_backward = "0".into();
-    label.curr_note.measure_note_position_triplet_32nd_forward = "0".into();
-    label.curr_note.measure_note_position_triplet_32nd_backward = "0".into();
-    label.curr_note.measure_note_position_percent_forward = "0".into();
-    label.curr_note.measure_note_position_percent_backward = "100".into();
-
-    label.curr_note.phrase_note_position_note_forward = XX.into();
-    label.curr_note.phrase_note_position_note_backward = XX.into();
-    label.curr_note.phrase_note_position_centisecond_forward = XX.into();
-    label.curr_note.phrase_note_position_centisecond_backward = XX.into();
-    label.curr_note.phrase_note_position_triplet_32nd_forward = XX.into();
-    label.curr_note.phrase_note_position_triplet_32nd_backward = XX.into();
-    label.curr_note.phrase_note_position_percent_forward = XX.into();
-    label.curr_note.phrase_note_position_percent_backward = XX.into();
-    label.curr_note.slur_with_previous = "0".into();
-    label.curr_note.slur_with_next = "0".into();
-    label.curr_note.dynamic_mark = "n".into();
-    label.curr_note.distance_to_next_accent_note = XX.into();
-    label.curr_note.distance_to_previous_accent_note = XX.into();
-    label.curr_note.distance_to_next_accent_centisecond = XX.into();
-    label.curr_note.distance_to_previous_accent_centisecond = XX.into();
-    label.curr_note.distance_to_next_accent_triplet_32nd = XX.into();
-    label.curr_note.distance_to_previous_accent_triplet_32nd = XX.into();
-    label.curr_note.distance_to_next_staccato_note = XX.into();
-    label.curr_note.distance_to_previous_staccato_note = XX.into();
-    label.curr_note.distance_to_next_staccato_centisecond = XX.into();
-    label.curr_note.distance_to_previous_staccato_centisecond = XX.into();
-    label.curr_note.distance_to_next_staccato_triplet_32nd = XX.into();
-    label.curr_note.distance_to_previous_staccato_triplet_32nd = XX.into();
-    label.curr_note.crescendo_position_note_forward = XX.into();
-    label.curr_note.crescendo_position_note_backward = XX.into();
-    label.curr_note.crescendo_position_second_forward = XX.into();
-    label.curr_note.crescendo_position_second_backward = XX.into();
-    label.curr_note.crescendo_position_triplet_32nd_forward = XX.into();
-    label.curr_note.crescendo_position_triplet_32nd_backward = XX.into();
-    label.curr_note.crescendo_position_percent_forward = XX.into();
-    label.curr_note.crescendo_position_percent_backward = XX.into();
-    label.curr_note.decrescendo_position_note_forward = XX.into();
-    label.curr_note.decrescendo_position_note_backward = XX.into();
-    label.curr_note.decrescendo_position_second_forward = XX.into();
-    label.curr_note.decrescendo_position_second_backward = XX.into();
-    label.curr_note.decrescendo_position_triplet_32nd_forward = XX.into();
-    label.curr_note.decrescendo_position_triplet_32nd_backward = XX.into();
-    label.curr_note.decrescendo_position_percent_forward = XX.into();
-    label.curr_note.decrescendo_position_percent_backward = XX.into();
+    // NOTE: centisecondとあるが、Neutrinoのラベルでは0.1秒単位になっている
+    let measure = &note_timings[note_index].measure;
+    [
+        label.curr_note.measure_note_position_note_forward,
+        label.curr_note.measure_note_position_note_backward,
+        label.curr_note.measure_note_position_centisecond_forward,
+        label.curr_note.measure_note_position_centisecond_backward,
+        label.curr_note.measure_note_position_triplet_32nd_forward,
+        label.curr_note.measure_note_position_triplet_32nd_backward,
+        label.curr_note.measure_note_position_percent_forward,
+        label.curr_note.measure_note_position_percent_backward,
+    ] = position_fields(Some(measure));
+
+    [
+        label.curr_note.phrase_note_position_note_forward,
+        label.curr_note.phrase_note_position_note_backward,
+        label.curr_note.phrase_note_position_centisecond_forward,
+        label.curr_note.phrase_note_position_centisecond_backward,
+        label.curr_note.phrase_note_position_triplet_32nd_forward,
+        label.curr_note.phrase_note_position_triplet_32nd_backward,
+        label.curr_note.phrase_note_position_percent_forward,
+        label.curr_note.phrase_note_position_percent_backward,
+    ] = position_fields(note_timings[note_index].phrase.as_ref());
+    label.curr_note.slur_with_previous = if notes[note_index].slur { "1" } else { "0" }.into();
+    label.curr_note.slur_with_next = if notes.get(note_index + 1).is_some_and(|n| n.slur) {
+        "1"
+    } else {
+        "0"
+    }
+    .into();
+    label.curr_note.dynamic_mark = notes[note_index]
+        .marks
+        .dynamic
+        .map_or("n", Dynamic::label_symbol)
+        .into();
+    let accent = |note: &Note| note.marks.accent;
+    let staccato = |note: &Note| note.marks.staccato;
+    [
+        label.curr_note.distance_to_next_accent_note,
+        label.curr_note.distance_to_next_accent_centisecond,
+        label.curr_note.distance_to_next_accent_triplet_32nd,
+    ] = mark_distance(notes, note_timings, note_index, Direction::Next, accent);
+    [
+        label.curr_note.distance_to_previous_accent_note,
+        label.curr_note.distance_to_previous_accent_centisecond,
+        label.curr_note.distance_to_previous_accent_triplet_32nd,
+    ] = mark_distance(notes, note_timings, note_index, Direction::Previous, accent);
+    [
+        label.curr_note.distance_to_next_staccato_note,
+        label.curr_note.distance_to_next_staccato_centisecond,
+        label.curr_note.distance_to_next_staccato_triplet_32nd,
+    ] = mark_distance(notes, note_timings, note_index, Direction::Next, staccato);
+    [
+        label.curr_note.distance_to_previous_staccato_note,
+        label.curr_note.distance_to_previous_staccato_centisecond,
+        label.curr_note.distance_to_previous_staccato_triplet_32nd,
+    ] = mark_distance(
+        notes,
+        note_timings,
+        note_index,
+        Direction::Previous,
+        staccato,
+    );
+    [
+        label.curr_note.crescendo_position_note_forward,
+        label.curr_note.crescendo_position_note_backward,
+        label.curr_note.crescendo_position_second_forward,
+        label.curr_note.crescendo_position_second_backward,
+        label.curr_note.crescendo_position_triplet_32nd_forward,
+        label.curr_note.crescendo_position_triplet_32nd_backward,
+        label.curr_note.crescendo_position_percent_forward,
+        label.curr_note.crescendo_position_percent_backward,
+    ] = position_fields(note_timings[note_index].crescendo.as_ref());
+    [
+        label.curr_note.decrescendo_position_note_forward,
+        label.curr_note.decrescendo_position_note_backward,
+        label.curr_note.decrescendo_position_second_forward,
+        label.curr_note.decrescendo_position_second_backward,
+        label.curr_note.decrescendo_position_triplet_32nd_forward,
+        label.curr_note.decrescendo_position_triplet_32nd_backward,
+        label.curr_note.decrescendo_position_percent_forward,
+        label.curr_note.decrescendo_position_percent_backward,
+    ] = position_fields(note_timings[note_index].decrescendo.as_ref());
     label.curr_note.pitch_difference_from_previous_note = XX.into();
-    label.curr_note.pitch_difference_to_next_note = notes
-        .get(note_index + 1)
-        .and_then(|next_note| {
+    label
+        .curr_note
+        .pitch_difference_to_next_note
+        .set_pitch_difference(notes.get(note_index + 1).and_then(|next_note| {
             notes[note_index]
                 .pitch
                 .zip(next_note.pitch)
-                .map(|(current, next)| format_pitch_difference(current, next).into())
-        })
-        .unwrap_or_else(|| XX.into());
+                .map(|(current, next)| i32::from(next) - i32::from(current))
+        }));
     label.curr_note.reserved_2 = XX.into();
     label.curr_note.reserved_3 = XX.into();
 }
 
-fn fill_phrase_and_song_contexts(label: &mut Label, notes: &[Note], options: &ComposeOptions) {
-    let total_phonemes = notes.iter().map(|n| n.phonemes.len()).sum::<usize>();
+fn position_fields(position: Option<&NotePosition>) -> [LabelValue; 8] {
+    let Some(position) = position else {
+        return std::array::from_fn(|_| XX.into());
+    };
+    let mut fields: [LabelValue; 8] = Default::default();
+    fields[0].set_count(Some(position.note_forward));
+    fields[1].set_count(Some(position.note_backward));
+    fields[2].set_integer(Some(position.decisecond_forward));
+    fields[3].set_integer(Some(position.decisecond_backward));
+    fields[4].set_integer(Some(position.triplet_32nd_forward));
+    fields[5].set_integer(Some(position.triplet_32nd_backward));
+    fields[6].set_integer(Some(position.percent_forward));
+    fields[7].set_integer(Some(position.percent_backward));
+    fields
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Previous,
+    Next,
+}
 
-    label.prev_phrase.syllable_count = XX.into();
-    label.prev_phrase.phoneme_count = XX.into();
-    label.curr_phrase.syllable_count = notes.len().to_string().into();
-    label.curr_phrase.phoneme_count = total_phonemes.to_string().into();
-    label.next_phrase.syllable_count = XX.into();
-    label.next_phrase.phoneme_count = XX.into();
+// NOTE: 記号のついたノートまでの距離（ノート数、0.1秒、32分3連符）
+fn mark_distance(
+    notes: &[Note],
+    note_timings: &[NoteTiming],
+    note_index: usize,
+    direction: Direction,
+    has_mark: impl Fn(&Note) -> bool,
+) -> [LabelValue; 3] {
+    let target = match direction {
+        Direction::Previous => (0..note_index).rev().find(|&i| has_mark(&notes[i])),
+        Direction::Next => (note_index + 1..notes.len()).find(|&i| has_mark(&notes[i])),
+    };
+    let Some(target) = target else {
+        return [XX.into(), XX.into(), XX.into()];
+    };
+    let current = &note_timings[note_index];
+    let target_timing = &note_timings[target];
+    let mut fields: [LabelValue; 3] = Default::default();
+    fields[0].set_count(Some(target.abs_diff(note_index)));
+    fields[1].set_integer(Some(ns_to_deciseconds(
+        current.start_time_ns.abs_diff(target_timing.start_time_ns) as i64,
+    )));
+    fields[2].set_count(Some(
+        current
+            .position_triplet_32nd
+            .abs_diff(target_timing.position_triplet_32nd) as usize,
+    ));
+    fields
+}
+
+fn fill_phrase_and_song_contexts(
+    label: &mut Label,
+    notes: &[Note],
+    phrases: &[std::ops::Range<usize>],
+    note_index: usize,
+    options: &ComposeOptions,
+) {
+    let current = phrases.iter().position(|p| p.contains(&note_index));
+    let previous = match current {
+        Some(current) => current.checked_sub(1),
+        None => phrases.iter().rposition(|p| p.end <= note_index),
+    };
+    let next = match current {
+        Some(current) => Some(current + 1).filter(|i| *i < phrases.len()),
+        None => phrases.iter().position(|p| p.start > note_index),
+    };
+
+    fill_phrase(
+        &mut label.prev_phrase.syllable_count,
+        &mut label.prev_phrase.phoneme_count,
+        notes,
+        previous.map(|i| &phrases[i]),
+    );
+    fill_phrase(
+        &mut label.curr_phrase.syllable_count,
+        &mut label.curr_phrase.phoneme_count,
+        notes,
+        current.map(|i| &phrases[i]),
+    );
+    fill_phrase(
+        &mut label.next_phrase.syllable_count,
+        &mut label.next_phrase.phoneme_count,
+        notes,
+        next.map(|i| &phrases[i]),
+    );
     label.song.syllable_per_measure = XX.into();
     label.song.phoneme_per_measure = XX.into();
-    label.song.phrase_count = options.phrase_count.to_string().into();
+    label
+        .song
+        .phrase_count
+        .set_count(Some(options.phrase_count));
+}
+
+fn fill_phrase(
+    syllable_count: &mut LabelValue,
+    phoneme_count: &mut LabelValue,
+    notes: &[Note],
+    phrase: Option<&std::ops::Range<usize>>,
+) {
+    syllable_count.set_count(phrase.map(|phrase| phrase.len()));
+    phoneme_count.set_count(phrase.map(|phrase| {
+        notes[phrase.clone()]
+            .iter()
+            .map(|n| n.phonemes.len())
+            .sum::<usize>()
+    }));
 }
 
 fn fill_syllable(
@@ -427,9 +1061,9 @@ fn fill_syllable(
     idx: Option<usize>,
 ) {
     if let Some(i) = idx {
-        *phoneme_count = notes[i].phonemes.len().to_string().into();
-        *note_position_forward = (i + 1).to_string().into();
-        *note_position_backward = (notes.len() - i).to_string().into();
+        phoneme_count.set_count(Some(notes[i].phonemes.len()));
+        note_position_forward.set_count(Some(i + 1));
+        note_position_backward.set_count(Some(notes.len() - i));
         *language = notes[i]
             .language
             .clone()
@@ -461,27 +1095,27 @@ fn fill_note(
     length_triplet_32nd: &mut LabelValue,
     reserved: &mut LabelValue,
     notes: &[Note],
+    note_timings: &[NoteTiming],
     idx: Option<usize>,
-    options: &ComposeOptions,
 ) {
     if let Some(i) = idx {
+        let note_timing = &note_timings[i];
         let length_triplet_32nd_value: i32 = notes[i].length.into();
-        if let Some(pitch) = notes[i].pitch {
-            *absolute_pitch = midi_to_note_name(pitch).into();
-            *relative_pitch = pitch.rem_euclid(12).to_string().into();
-        } else {
-            *absolute_pitch = XX.into();
-            *relative_pitch = XX.into();
-        }
-        *key_signature = options.key_signature.clone().into();
-        *beat = options.beat.clone().into();
-        *tempo = options.tempo.to_string().into();
-        *length_syllable = "1".into();
-        *length_centisecond =
-            length_triplet_32nd_to_centiseconds(length_triplet_32nd_value, options.tempo)
-                .to_string()
-                .into();
-        *length_triplet_32nd = length_triplet_32nd_value.to_string().into();
+        let pitch = notes[i].pitch;
+        absolute_pitch.set_pitch(pitch);
+        relative_pitch.set_count(pitch.map(|pitch| {
+            (i32::from(pitch) - i32::from(note_timing.key_signature.tonic())).rem_euclid(12)
+                as usize
+        }));
+        key_signature.set_integer(Some(note_timing.key_signature.fifths.into()));
+        beat.set_beat(Some(note_timing.beat));
+        tempo.set_number(Some(note_timing.tempo));
+        length_syllable.set_count(Some(1));
+        // NOTE: テンポ変化をまたぐノートもあるので、音価ではなく実際の長さから求める
+        length_centisecond.set_integer(Some(ns_to_centiseconds(
+            (note_timing.end_time_ns - note_timing.start_time_ns) as i64,
+        )));
+        length_triplet_32nd.set_integer(Some(length_triplet_32nd_value.into()));
         *reserved = XX.into();
     } else {
         *absolute_pitch = XX.into();
@@ -496,15 +1130,6 @@ fn fill_note(
     }
 }
 
-fn length_triplet_32nd_to_centiseconds(length_triplet_32nd: i32, tempo: f64) -> i32 {
-    if tempo <= 0.0 {
-        return 0;
-    }
-    // 24 triplet-32nd notes = 1 quarter note.
-    // centiseconds = length * (60 * 100) / (24 * tempo) = length * 250 / tempo
-    (length_triplet_32nd * 250 + (tempo / 2.0) as i32) / tempo as i32
-}
-
 fn length_triplet_32nd_to_nanoseconds(length_triplet_32nd: i32, tempo: f64) -> u64 {
     if tempo <= 0.0 {
         return 0;
@@ -519,15 +1144,6 @@ fn length_triplet_32nd_to_nanoseconds(length_triplet_32nd: i32, tempo: f64) -> u
     }
 }
 
-fn format_pitch_difference(current_pitch: u8, target_pitch: u8) -> String {
-    let diff = target_pitch as i16 - current_pitch as i16;
-    if diff >= 0 {
-        format!("p{diff}")
-    } else {
-        format!("m{}", -diff)
-    }
-}
-
 fn symbol_at(points: &[Point], index: usize, offset: isize) -> &str {
     let shifted = index as isize + offset;
     if shifted < 0 || shifted >= points.len() as isize {
@@ -537,15 +1153,41 @@ fn symbol_at(points: &[Point], index: usize, offset: isize) -> &str {
     }
 }
 
-fn midi_to_note_name(pitch: u8) -> String {
+pub fn midi_to_note_name(pitch: u8) -> String {
     const NOTE_NAMES: [&str; 12] = [
         "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B",
     ];
     let pc = pitch.rem_euclid(12) as usize;
-    let octave = pitch.div_euclid(12) - 1;
+    let octave = i32::from(pitch / 12) - 1;
     format!("{}{}", NOTE_NAMES[pc], octave)
 }
 
+// NOTE: midi_to_note_name の逆。#とbの両方を受け付ける
+pub fn note_name_to_midi(name: &str) -> Option<u8> {
+    let name = name.trim();
+    let mut chars = name.chars();
+    let base = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.chars().next() {
+        Some('#') => (1, &rest[1..]),
+        Some('b') => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+    let octave = octave.parse::<i32>().ok()?;
+    u8::try_from((octave + 1) * 12 + base + accidental)
+        .ok()
+        .filter(|pitch| *pitch <= 127)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -558,35 +1200,51 @@ mod tests {
                     pitch: None,
                     start_time_ns: 0,
                     length: NoteLength::from_4th_note(1),
+                    end_time_ns: None,
                     phonemes: vec!["pau".to_string()],
                     language: Some("JPN".to_string()),
                     language_dependent_context: Some("0".to_string()),
+                    slur: false,
+                    marks: Default::default(),
                 },
                 Note {
                     pitch: Some(60),
                     start_time_ns: 0,
                     length: NoteLength::from_4th_note(1),
+                    end_time_ns: None,
                     phonemes: vec!["p".to_string(), "a".to_string()],
                     language: Some("JPN".to_string()),
                     language_dependent_context: Some("0".to_string()),
+                    slur: false,
+                    marks: Default::default(),
                 },
                 Note {
                     pitch: None,
                     start_time_ns: 0,
                     length: NoteLength::from_4th_note(1),
+                    end_time_ns: None,
                     phonemes: vec!["pau".to_string()],
                     language: Some("JPN".to_string()),
                     language_dependent_context: Some("0".to_string()),
+                    slur: false,
+                    marks: Default::default(),
                 },
             ],
-            tempo: 140.0,
+            tempos: vec![Tempo {
+                start_time_ns: 0,
+                tempo: 140.0,
+            }],
             time_signatures: vec![TimeSignature {
+                start_time_ns: 0,
                 numerator: 3,
                 denominator: 4,
             }],
+            key_signatures: vec![KeySignature::default()],
+            hairpins: Vec::new(),
         };
         let labels = compose_labels_from_score(&score).expect("compose should succeed");
         assert_eq!(labels.len(), 4);
+        assert!(labels.iter().all(|l| l.label.validate().is_ok()));
         assert_eq!(
             labels[0].label.phoneme.phoneme_id_current.as_option(),
             Some("pau")
@@ -619,17 +1277,515 @@ mod tests {
         insta::assert_debug_snapshot!(labels);
     }
 
+    #[test]
+    fn tempo_changes_apply_per_note() {
+        let note = |length: NoteLength| Note {
+            pitch: Some(60),
+            start_time_ns: 0,
+            length,
+            end_time_ns: None,
+            phonemes: vec!["a".to_string()],
+            language: Some("JPN".to_string()),
+            language_dependent_context: Some("0".to_string()),
+            slur: false,
+            marks: Default::default(),
+        };
+        let score = Score {
+            notes: vec![
+                note(NoteLength::from_4th_note(1)),
+                note(NoteLength::from_4th_note(2)),
+                note(NoteLength::from_4th_note(1)),
+            ],
+            tempos: vec![
+                Tempo {
+                    start_time_ns: 0,
+                    tempo: 120.0,
+                },
+                Tempo {
+                    start_time_ns: 1_000_000_000,
+                    tempo: 60.0,
+                },
+            ],
+            time_signatures: vec![
+                TimeSignature::default(),
+                TimeSignature {
+                    start_time_ns: 500_000_000,
+                    numerator: 6,
+                    denominator: 8,
+                },
+            ],
+            key_signatures: vec![KeySignature::default()],
+            hairpins: Vec::new(),
+        };
+        assert_eq!(
+            compute_score_time_ranges_ns(&score),
+            vec![
+                (0, 500_000_000),
+                (500_000_000, 2_000_000_000),
+                (2_000_000_000, 3_000_000_000),
+            ]
+        );
+        let labels = compose_labels_from_score(&score).expect("compose should succeed");
+        assert_eq!(labels[0].label.curr_note.tempo, "120");
+        assert_eq!(labels[0].label.curr_note.beat, "4/4");
+        assert_eq!(labels[1].label.curr_note.beat, "6/8");
+        assert_eq!(labels[2].label.curr_note.tempo, "60");
+        assert_eq!(labels[2].label.prev_note.tempo, "120");
+        assert_eq!(labels[2].label.curr_note.length_centisecond, "100");
+    }
+
+    #[test]
+    fn measure_positions() {
+        let note = |start_time_ns: u64, length: NoteLength| Note {
+            pitch: Some(60),
+            start_time_ns,
+            length,
+            end_time_ns: None,
+            phonemes: vec!["a".to_string()],
+            language: Some("JPN".to_string()),
+            language_dependent_context: Some("0".to_string()),
+            slur: false,
+            marks: Default::default(),
+        };
+        let score = Score {
+            notes: vec![
+                note(0, NoteLength::from_4th_note(4)),
+                note(2_400_000_000, NoteLength::from_4th_note(1)),
+                note(3_000_000_000, NoteLength::from_4th_note(1)),
+                note(3_600_000_000, NoteLength::from_4th_note(1)),
+                note(4_200_000_000, NoteLength::from_4th_note(1)),
+            ],
+            tempos: vec![Tempo {
+                start_time_ns: 0,
+                tempo: 100.0,
+            }],
+            time_signatures: vec![TimeSignature::default()],
+            key_signatures: vec![KeySignature::default()],
+            hairpins: Vec::new(),
+        };
+        let labels = compose_labels_from_score(&score).expect("compose should succeed");
+        let measure_fields = |i: usize| {
+            let note = &labels[i].label.curr_note;
+            [
+                note.measure_note_position_note_forward.to_string(),
+                note.measure_note_position_note_backward.to_string(),
+                note.measure_note_position_centisecond_forward.to_string(),
+                note.measure_note_position_centisecond_backward.to_string(),
+                note.measure_note_position_triplet_32nd_forward.to_string(),
+                note.measure_note_position_triplet_32nd_backward.to_string(),
+                note.measure_note_position_percent_forward.to_string(),
+                note.measure_note_position_percent_backward.to_string(),
+            ]
+        };
+        assert_eq!(
+            measure_fields(0),
+            ["1", "1", "0", "24", "0", "96", "0", "100"]
+        );
+        assert_eq!(
+            measure_fields(1),
+            ["1", "4", "0", "24", "0", "96", "0", "100"]
+        );
+        assert_eq!(
+            measure_fields(2),
+            ["2", "3", "6", "18", "24", "72", "25", "75"]
+        );
+        assert_eq!(
+            measure_fields(4),
+            ["4", "1", "18", "6", "72", "24", "75", "25"]
+        );
+    }
+
+    #[test]
+    fn phrases_are_split_by_rests() {
+        let note =
+            |pitch: Option<u8>, start_time_ns: u64, length: NoteLength, phonemes: &[&str]| Note {
+                pitch,
+                start_time_ns,
+                length,
+                end_time_ns: None,
+                phonemes: phonemes.iter().map(|p| p.to_string()).collect(),
+                language: Some("JPN".to_string()),
+                language_dependent_context: Some("0".to_string()),
+                slur: false,
+                marks: Default::default(),
+            };
+        let score = Score {
+            notes: vec![
+                note(None, 0, NoteLength::from_4th_note(1), &["pau"]),
+                note(Some(60), 500_000_000, NoteLength::from_4th_note(1), &["a"]),
+                note(
+                    Some(62),
+                    1_000_000_000,
+                    NoteLength::from_4th_note(1),
+                    &["i"],
+                ),
+                note(None, 1_500_000_000, NoteLength::from_4th_note(1), &["pau"]),
+                note(
+                    Some(64),
+                    2_000_000_000,
+                    NoteLength::from_4th_note(2),
+                    &["k", "a"],
+                ),
+                note(None, 3_000_000_000, NoteLength::from_4th_note(1), &["pau"]),
+            ],
+            tempos: vec![Tempo::default()],
+            time_signatures: vec![TimeSignature::default()],
+            key_signatures: vec![KeySignature::default()],
+            hairpins: Vec::new(),
+        };
+        let labels = compose_labels_from_score(&score).expect("compose should succeed");
+        let phrase_counts = |i: usize| {
+            let label = &labels[i].label;
+            [
+                label.prev_phrase.syllable_count.to_string(),
+                label.prev_phrase.phoneme_count.to_string(),
+                label.curr_phrase.syllable_count.to_string(),
+                label.curr_phrase.phoneme_count.to_string(),
+                label.next_phrase.syllable_count.to_string(),
+                label.next_phrase.phoneme_count.to_string(),
+            ]
+        };
+        assert_eq!(phrase_counts(1), ["xx", "xx", "2", "2", "1", "2"]);
+        assert_eq!(phrase_counts(3), ["2", "2", "xx", "xx", "1", "2"]);
+        assert_eq!(phrase_counts(5), ["2", "2", "1", "2", "xx", "xx"]);
+        assert_eq!(labels[0].label.song.phrase_count.to_string(), "2");
+
+        let phrase_fields = |i: usize| {
+            let note = &labels[i].label.curr_note;
+            [
+                note.phrase_note_position_note_forward.to_string(),
+                note.phrase_note_position_note_backward.to_string(),
+                note.phrase_note_position_centisecond_forward.to_string(),
+                note.phrase_note_position_centisecond_backward.to_string(),
+                note.phrase_note_position_triplet_32nd_forward.to_string(),
+                note.phrase_note_position_triplet_32nd_backward.to_string(),
+                note.phrase_note_position_percent_forward.to_string(),
+                note.phrase_note_position_percent_backward.to_string(),
+            ]
+        };
+        assert_eq!(
+            phrase_fields(2),
+            ["2", "1", "5", "5", "24", "24", "50", "50"]
+        );
+        assert_eq!(
+            phrase_fields(4),
+            ["1", "1", "0", "10", "0", "48", "0", "100"]
+        );
+        assert_eq!(phrase_fields(3), ["xx"; 8]);
+    }
+
+    #[test]
+    fn slur_flags() {
+        let note = |pitch: u8, phoneme: &str, slur: bool| Note {
+            pitch: Some(pitch),
+            start_time_ns: 0,
+            length: NoteLength::from_4th_note(1),
+            end_time_ns: None,
+            phonemes: vec![phoneme.to_string()],
+            language: Some("JPN".to_string()),
+            language_dependent_context: Some("0".to_string()),
+            slur,
+            marks: Default::default(),
+        };
+        let score = Score {
+            notes: vec![
+                note(60, "a", false),
+                note(62, "a", true),
+                note(64, "i", false),
+            ],
+            ..Default::default()
+        };
+        let labels = compose_labels_from_score(&score).expect("compose should succeed");
+        let flags = labels
+            .iter()
+            .map(|l| {
+                (
+                    l.label.curr_note.slur_with_previous.to_string(),
+                    l.label.curr_note.slur_with_next.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flags,
+            [
+                ("0".to_string(), "1".to_string()),
+                ("1".to_string(), "0".to_string()),
+                ("0".to_string(), "0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn dynamics_and_mark_distances() {
+        let note = |start_time_ns: u64, marks: NoteMarks| Note {
+            pitch: Some(60),
+            start_time_ns,
+            length: NoteLength::from_4th_note(1),
+            end_time_ns: None,
+            phonemes: vec!["a".to_string()],
+            language: Some("JPN".to_string()),
+            language_dependent_context: Some("0".to_string()),
+            slur: false,
+            marks,
+        };
+        let accent = NoteMarks {
+            accent: true,
+            ..Default::default()
+        };
+        let staccato = NoteMarks {
+            dynamic: Some(Dynamic::MezzoForte),
+            staccato: true,
+            ..Default::default()
+        };
+        let score = Score {
+            notes: vec![
+                note(0, accent.clone()),
+                note(500_000_000, NoteMarks::default()),
+                note(1_000_000_000, staccato),
+                note(1_500_000_000, accent),
+            ],
+            ..Default::default()
+        };
+        let labels = compose_labels_from_score(&score).expect("compose should succeed");
+        let fields = |i: usize| {
+            let note = &labels[i].label.curr_note;
+            [
+                note.dynamic_mark.to_string(),
+                note.distance_to_next_accent_note.to_string(),
+                note.distance_to_next_accent_centisecond.to_string(),
+                note.distance_to_next_accent_triplet_32nd.to_string(),
+                note.distance_to_previous_accent_note.to_string(),
+                note.distance_to_previous_accent_centisecond.to_string(),
+                note.distance_to_previous_accent_triplet_32nd.to_string(),
+                note.distance_to_next_staccato_note.to_string(),
+                note.distance_to_previous_staccato_note.to_string(),
+            ]
+        };
+        assert_eq!(
+            fields(0),
+            ["n", "3", "15", "72", "xx", "xx", "xx", "2", "xx"]
+        );
+        assert_eq!(fields(1), ["n", "2", "10", "48", "1", "5", "24", "1", "xx"]);
+        assert_eq!(
+            fields(2),
+            ["mf", "1", "5", "24", "2", "10", "48", "xx", "xx"]
+        );
+        assert_eq!(
+            fields(3),
+            ["n", "xx", "xx", "xx", "3", "15", "72", "xx", "1"]
+        );
+    }
+
+    #[test]
+    fn dynamic_label_symbols() {
+        assert_eq!(
+            ["pp", "p", "mp", "mf", "f", "ff"]
+                .map(|name| name.parse::<Dynamic>().expect("must parse").label_symbol()),
+            ["p2", "p1", "mp", "mf", "f1", "f2"]
+        );
+        assert!("fff".parse::<Dynamic>().is_err());
+    }
+
+    #[test]
+    fn hairpin_positions() {
+        let note = |pitch: Option<u8>, start_time_ns: u64, phoneme: &str| Note {
+            pitch,
+            start_time_ns,
+            length: NoteLength::from_4th_note(1),
+            end_time_ns: None,
+            phonemes: vec![phoneme.to_string()],
+            language: Some("JPN".to_string()),
+            language_dependent_context: Some("0".to_string()),
+            slur: false,
+            marks: Default::default(),
+        };
+        let score = Score {
+            notes: vec![
+                note(None, 0, "pau"),
+                note(Some(60), 500_000_000, "a"),
+                note(Some(62), 1_000_000_000, "a"),
+                note(Some(64), 1_500_000_000, "a"),
+                note(None, 2_000_000_000, "pau"),
+            ],
+            hairpins: vec![
+                Hairpin {
+                    kind: HairpinKind::Crescendo,
+                    start_time_ns: 500_000_000,
+                    end_time_ns: 2_000_000_000,
+                },
+                Hairpin {
+                    kind: HairpinKind::Decrescendo,
+                    start_time_ns: 1_500_000_000,
+                    end_time_ns: 2_000_000_000,
+                },
+            ],
+            ..Default::default()
+        };
+        let labels = compose_labels_from_score(&score).expect("compose should succeed");
+        let crescendo = |i: usize| {
+            let note = &labels[i].label.curr_note;
+            [
+                note.crescendo_position_note_forward.to_string(),
+                note.crescendo_position_note_backward.to_string(),
+                note.crescendo_position_second_forward.to_string(),
+                note.crescendo_position_second_backward.to_string(),
+                note.crescendo_position_triplet_32nd_forward.to_string(),
+                note.crescendo_position_triplet_32nd_backward.to_string(),
+                note.crescendo_position_percent_forward.to_string(),
+                note.crescendo_position_percent_backward.to_string(),
+            ]
+        };
+        let decrescendo = |i: usize| {
+            let note = &labels[i].label.curr_note;
+            [
+                note.decrescendo_position_note_forward.to_string(),
+                note.decrescendo_position_note_backward.to_string(),
+                note.decrescendo_position_second_forward.to_string(),
+                note.decrescendo_position_second_backward.to_string(),
+                note.decrescendo_position_triplet_32nd_forward.to_string(),
+                note.decrescendo_position_triplet_32nd_backward.to_string(),
+                note.decrescendo_position_percent_forward.to_string(),
+                note.decrescendo_position_percent_backward.to_string(),
+            ]
+        };
+        assert_eq!(crescendo(0), ["xx"; 8]);
+        assert_eq!(crescendo(1), ["1", "3", "0", "15", "0", "72", "0", "100"]);
+        assert_eq!(crescendo(2), ["2", "2", "5", "10", "24", "48", "33", "67"]);
+        assert_eq!(crescendo(3), ["3", "1", "10", "5", "48", "24", "66", "34"]);
+        assert_eq!(crescendo(4), ["xx"; 8]);
+        assert_eq!(decrescendo(2), ["xx"; 8]);
+        assert_eq!(decrescendo(3), ["1", "1", "0", "5", "0", "24", "0", "100"]);
+    }
+
+    #[test]
+    fn key_relative_pitch() {
+        let note = |pitch: u8| Note {
+            pitch: Some(pitch),
+            start_time_ns: 0,
+            length: NoteLength::from_4th_note(1),
+            end_time_ns: None,
+            phonemes: vec!["a".to_string()],
+            language: Some("JPN".to_string()),
+            language_dependent_context: Some("0".to_string()),
+            slur: false,
+            marks: Default::default(),
+        };
+        let score = Score {
+            notes: vec![note(62), note(63), note(60)],
+            key_signatures: vec![
+                KeySignature {
+                    start_time_ns: 0,
+                    fifths: 2,
+                },
+                KeySignature {
+                    start_time_ns: 1_000_000_000,
+                    fifths: -3,
+                },
+            ],
+            ..Default::default()
+        };
+        let labels = compose_labels_from_score(&score).expect("compose should succeed");
+        let key_fields = labels
+            .iter()
+            .map(|l| {
+                (
+                    l.label.curr_note.key_signature.to_string(),
+                    l.label.curr_note.relative_pitch.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            key_fields,
+            [
+                ("2".to_string(), "0".to_string()),
+                ("2".to_string(), "1".to_string()),
+                ("-3".to_string(), "9".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn estimates_key_signature() {
+        let notes = |pitches: &[u8]| {
+            pitches
+                .iter()
+                .map(|&pitch| Note {
+                    pitch: Some(pitch),
+                    start_time_ns: 0,
+                    length: NoteLength::from_4th_note(1),
+                    end_time_ns: None,
+                    phonemes: vec!["a".to_string()],
+                    language: Some("JPN".to_string()),
+                    language_dependent_context: Some("0".to_string()),
+                    slur: false,
+                    marks: Default::default(),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(estimate_key_signature(&[]).fifths, 0);
+        // F major
+        assert_eq!(
+            estimate_key_signature(&notes(&[65, 67, 69, 70, 72, 74, 76, 77])).fifths,
+            -1
+        );
+        // E major
+        assert_eq!(
+            estimate_key_signature(&notes(&[64, 66, 68, 69, 71, 73, 75, 76])).fifths,
+            4
+        );
+        // A minor -> C major
+        assert_eq!(
+            estimate_key_signature(&notes(&[69, 71, 72, 74, 76, 69, 64, 69])).fifths,
+            0
+        );
+    }
+
+    #[test]
+    fn measures_before_first_time_signature() {
+        let score = Score {
+            time_signatures: vec![TimeSignature {
+                start_time_ns: 1_000_000_000,
+                numerator: 3,
+                denominator: 4,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(score.measure_at(48), (48, 120));
+        assert_eq!(score.measure_at(0), (-24, 48));
+        assert_eq!(score.triplet_32nd_to_signed_ns(-24), -500_000_000);
+    }
+
     #[test]
     fn triplet_length_to_centiseconds() {
-        assert_eq!(length_triplet_32nd_to_centiseconds(24, 100.0), 60);
-        assert_eq!(length_triplet_32nd_to_centiseconds(96, 100.0), 240);
-        assert_eq!(length_triplet_32nd_to_centiseconds(24, 140.0), 43);
+        let centiseconds = |length, tempo| {
+            ns_to_centiseconds(length_triplet_32nd_to_nanoseconds(length, tempo) as i64)
+        };
+        assert_eq!(centiseconds(24, 100.0), 60);
+        assert_eq!(centiseconds(96, 100.0), 240);
+        assert_eq!(centiseconds(24, 140.0), 43);
+    }
+
+    #[test]
+    fn note_names_round_trip() {
+        for pitch in 0..=127 {
+            assert_eq!(note_name_to_midi(&midi_to_note_name(pitch)), Some(pitch));
+        }
+        assert_eq!(note_name_to_midi("C#4"), Some(61));
+        assert_eq!(note_name_to_midi("c-1"), Some(0));
+        assert_eq!(note_name_to_midi("Cb-1"), None);
+        assert_eq!(note_name_to_midi("H4"), None);
+        assert_eq!(note_name_to_midi("G10"), None);
     }
 
     #[test]
     fn pitch_difference_format() {
-        assert_eq!(format_pitch_difference(60, 62), "p2");
-        assert_eq!(format_pitch_difference(62, 60), "m2");
-        assert_eq!(format_pitch_difference(60, 60), "p0");
+        let format = |difference| {
+            let mut value = LabelValue::default();
+            value.set_pitch_difference(Some(difference));
+            value.to_string()
+        };
+        assert_eq!(format(2), "p2");
+        assert_eq!(format(-2), "m2");
+        assert_eq!(format(0), "p0");
     }
 }
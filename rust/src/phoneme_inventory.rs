@@ -0,0 +1,183 @@
+static INVENTORY_FILE_NAME: &str = "phonemes.txt";
+
+#[derive(Debug, Clone)]
+pub struct PhonemeInventory {
+    symbols: Vec<String>,
+}
+
+impl PhonemeInventory {
+    pub fn builtin() -> Self {
+        Self {
+            symbols: crate::kana::PHONEMES
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        }
+    }
+
+    // NOTE: 音源のphonemes.txtは空白・改行区切りの音素の一覧。#以降はコメント
+    pub fn parse(content: &str) -> Self {
+        Self {
+            symbols: content
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or_default())
+                .flat_map(str::split_whitespace)
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    // NOTE: 音源に一覧がなければ、日本語の音源だけ組み込みの一覧で検証する
+    pub fn load(voice_path: &std::path::Path, language: &str) -> anyhow::Result<Option<Self>> {
+        let path = voice_path.join(INVENTORY_FILE_NAME);
+        if path.exists() {
+            let content = std::fs::read_to_string(&path).map_err(|e| {
+                anyhow::anyhow!("Failed to read phoneme inventory {}: {}", path.display(), e)
+            })?;
+            return Ok(Some(Self::parse(&content)));
+        }
+        Ok((language == "JPN").then(Self::builtin))
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.symbols.iter().any(|s| s == symbol)
+    }
+
+    // NOTE: 短い記号はどれとも近くなるので、許す編集距離を記号の長さに応じて絞る
+    pub fn suggestions(&self, symbol: &str) -> Vec<&str> {
+        let max_distance = symbol.chars().count() / 2;
+        let lowercase = symbol.to_lowercase();
+        let mut candidates = self
+            .symbols
+            .iter()
+            .map(|s| {
+                let distance = if s.to_lowercase() == lowercase {
+                    0
+                } else {
+                    edit_distance(s, symbol)
+                };
+                (distance, s.as_str())
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(distance, _)| *distance);
+        candidates
+            .into_iter()
+            .take(3)
+            .map(|(_, symbol)| symbol)
+            .collect()
+    }
+
+    pub fn unknown_phoneme_message(&self, symbol: &str) -> String {
+        let suggestions = self.suggestions(symbol);
+        if suggestions.is_empty() {
+            format!("unknown phoneme \"{}\"", symbol)
+        } else {
+            format!(
+                "unknown phoneme \"{}\" (did you mean {})",
+                symbol,
+                suggestions
+                    .iter()
+                    .map(|s| format!("\"{}\"", s))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    }
+
+    // NOTE: 休符は合成側で入れるので、歌詞のあるノートだけを検証し、TuneLabのノート番号で報告する
+    pub fn validate(&self, score: &crate::neutrino_score::Score) -> anyhow::Result<()> {
+        let errors = score
+            .notes
+            .iter()
+            .zip(crate::synthesizer::payload_note_indices(score))
+            .filter_map(|(note, index)| index.map(|index| (note, index)))
+            .flat_map(|(note, index)| {
+                note.phonemes
+                    .iter()
+                    .filter(|p| !self.contains(p))
+                    .map(move |p| (index, p))
+            })
+            .map(|(index, symbol)| {
+                format!("note {}: {}", index, self.unknown_phoneme_message(symbol))
+            })
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            anyhow::bail!("Invalid phonemes:\n{}", errors.join("\n"));
+        }
+        Ok(())
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            current[j + 1] = (previous[j] + usize::from(ca != *cb))
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_near_misses() {
+        let inventory = PhonemeInventory::builtin();
+        assert!(inventory.contains("ky"));
+        assert!(!inventory.contains("kya"));
+        assert_eq!(inventory.suggestions("A"), ["a"]);
+        assert_eq!(&inventory.suggestions("kya")[..1], ["ky"]);
+        assert!(inventory.suggestions("xxxxx").is_empty());
+        assert_eq!(edit_distance("sh", "ch"), 1);
+        assert_eq!(edit_distance("", "pau"), 3);
+    }
+
+    #[test]
+    fn parses_voice_tables() {
+        let inventory = PhonemeInventory::parse("a i u # vowels\n\nk  s\n");
+        assert!(inventory.contains("s"));
+        assert!(!inventory.contains("#"));
+        assert!(!inventory.contains("vowels"));
+    }
+
+    #[test]
+    fn reports_note_index_and_symbol() {
+        let score = crate::synthesizer::task_notes_to_score(
+            &serde_json::from_str::<Vec<crate::synthesizer::SynthesisNotePayload>>(
+                r#"[
+                    { "startTime": 0.0, "endTime": 0.5, "pitch": 60, "lyric": "か",
+                      "properties": {}, "phonemes": [] },
+                    { "startTime": 1.0, "endTime": 1.5, "pitch": 60, "lyric": "",
+                      "properties": {}, "phonemes": [
+                        { "symbol": "Sh", "startTime": 0.9, "endTime": 1.0 },
+                        { "symbol": "a", "startTime": 1.0, "endTime": 1.5 }
+                      ] }
+                ]"#,
+            )
+            .expect("must parse"),
+            &[],
+            &[],
+            &[],
+            &crate::synthesizer::ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        let error = PhonemeInventory::builtin()
+            .validate(&score)
+            .expect_err("must fail")
+            .to_string();
+        assert_eq!(
+            error,
+            "Invalid phonemes:\nnote 1: unknown phoneme \"Sh\" (did you mean \"sh\", \"ch\", \"h\")"
+        );
+        assert!(PhonemeInventory::parse("k a Sh").validate(&score).is_ok());
+    }
+}
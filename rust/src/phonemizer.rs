@@ -0,0 +1,134 @@
+pub trait Phonemizer: std::fmt::Debug + Send + Sync {
+    // NOTE: ラベルのlanguageに入る言語コード
+    fn language(&self) -> &str;
+    fn phonemize(&self, lyric: &str) -> anyhow::Result<Vec<String>>;
+    // NOTE: 複数のノートに分けて割り当てる単位（日本語ならモーラ）
+    fn split_syllables(&self, lyric: &str) -> anyhow::Result<Vec<String>> {
+        Ok(vec![lyric.to_string()])
+    }
+    // NOTE: 伸ばし記号で引き継ぐ音素や、子音の伸縮・最小長の判定に使う
+    fn is_vowel(&self, phoneme: &str) -> bool;
+}
+
+static JAPANESE_VOWELS: &[&str] = &["a", "i", "u", "e", "o", "N"];
+
+#[derive(Debug)]
+pub struct JapanesePhonemizer;
+
+impl Phonemizer for JapanesePhonemizer {
+    fn language(&self) -> &str {
+        "JPN"
+    }
+
+    fn phonemize(&self, lyric: &str) -> anyhow::Result<Vec<String>> {
+        crate::kana::lyric_to_phonemes(lyric)
+    }
+
+    fn split_syllables(&self, lyric: &str) -> anyhow::Result<Vec<String>> {
+        crate::kana::lyric_to_morae(lyric)
+    }
+
+    fn is_vowel(&self, phoneme: &str) -> bool {
+        JAPANESE_VOWELS.contains(&phoneme)
+    }
+}
+
+// NOTE: 対応する音素化がない言語では、歌詞を空白区切りの音素列として扱う
+#[derive(Debug)]
+pub struct PhonemeListPhonemizer {
+    language: String,
+}
+
+impl Phonemizer for PhonemeListPhonemizer {
+    fn language(&self) -> &str {
+        &self.language
+    }
+
+    fn phonemize(&self, lyric: &str) -> anyhow::Result<Vec<String>> {
+        let phonemes = lyric
+            .split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if phonemes.is_empty() {
+            anyhow::bail!("Lyric has no phonemes: {:?}", lyric);
+        }
+        Ok(phonemes)
+    }
+
+    // NOTE: 音素の表記は言語ごとにまちまちなので、ARPAbetやピンインのように母音字で始まるものを母音とみなす
+    fn is_vowel(&self, phoneme: &str) -> bool {
+        phoneme
+            .chars()
+            .next()
+            .is_some_and(|c| "aeiouAEIOU".contains(c))
+    }
+}
+
+#[derive(Debug)]
+pub struct Registry {
+    phonemizers: std::collections::HashMap<String, std::sync::Arc<dyn Phonemizer>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let phonemizers: [std::sync::Arc<dyn Phonemizer>; 1] =
+            [std::sync::Arc::new(JapanesePhonemizer)];
+        Self {
+            phonemizers: phonemizers
+                .into_iter()
+                .map(|phonemizer| (phonemizer.language().to_string(), phonemizer))
+                .collect(),
+        }
+    }
+}
+
+impl Registry {
+    pub fn get(&self, language: &str) -> std::sync::Arc<dyn Phonemizer> {
+        let code = normalize_language(language);
+        self.phonemizers
+            .get(&code)
+            .cloned()
+            .unwrap_or_else(|| std::sync::Arc::new(PhonemeListPhonemizer { language: code }))
+    }
+}
+
+// NOTE: info.tomlのlanguageは表記がまちまちなので言語コードにそろえる。未指定は日本語とみなす
+pub fn normalize_language(language: &str) -> String {
+    match language.trim().to_ascii_lowercase().as_str() {
+        "" | "ja" | "jp" | "jpn" | "japanese" => "JPN".to_string(),
+        "en" | "eng" | "english" => "ENG".to_string(),
+        "zh" | "chn" | "zho" | "chinese" => "CHN".to_string(),
+        "ko" | "kor" | "korean" => "KOR".to_string(),
+        other => other.to_ascii_uppercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_resolves_language_aliases() {
+        let registry = Registry::default();
+        for language in ["", "ja", "Japanese", "JPN"] {
+            assert_eq!(registry.get(language).language(), "JPN");
+        }
+        assert_eq!(
+            registry.get("ja").phonemize("か").expect("must phonemize"),
+            ["k", "a"]
+        );
+
+        let english = registry.get("English");
+        assert_eq!(english.language(), "ENG");
+        assert_eq!(
+            english.phonemize("hh ah").expect("must phonemize"),
+            ["hh", "ah"]
+        );
+        assert!(english.phonemize(" ").is_err());
+        assert!(english.is_vowel("ah"));
+        assert!(english.is_vowel("IY1"));
+        assert!(!english.is_vowel("hh"));
+        assert!(registry.get("ja").is_vowel("N"));
+        assert!(!registry.get("ja").is_vowel("ah"));
+    }
+}
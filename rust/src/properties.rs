@@ -0,0 +1,416 @@
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum PropertyKind {
+    Number {
+        default: f64,
+        min: f64,
+        max: f64,
+        is_integer: bool,
+    },
+    ComboBox {
+        default: String,
+        options: Vec<String>,
+    },
+    CheckBox {
+        default: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PropertyDefinition {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: PropertyKind,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertySchema {
+    pub part_properties: Vec<PropertyDefinition>,
+    pub note_properties: Vec<PropertyDefinition>,
+}
+
+fn number(name: &str, default: f64, min: f64, max: f64, is_integer: bool) -> PropertyDefinition {
+    PropertyDefinition {
+        name: name.to_string(),
+        kind: PropertyKind::Number {
+            default,
+            min,
+            max,
+            is_integer,
+        },
+    }
+}
+
+fn combo_box(name: &str, options: &[&str]) -> PropertyDefinition {
+    PropertyDefinition {
+        name: name.to_string(),
+        kind: PropertyKind::ComboBox {
+            default: options[0].to_string(),
+            options: options.iter().map(|o| o.to_string()).collect(),
+        },
+    }
+}
+
+fn check_box(name: &str) -> PropertyDefinition {
+    PropertyDefinition {
+        name: name.to_string(),
+        kind: PropertyKind::CheckBox { default: false },
+    }
+}
+
+// NOTE: TuneLabに出すプロパティはすべてここで定義する。C#側はこの定義からプロパティの設定を組み立てる
+impl Default for PropertySchema {
+    fn default() -> Self {
+        Self {
+            part_properties: vec![
+                number("styleShift", 0.0, -24.0, 24.0, true),
+                number("waveformStyleShift", 0.0, -24.0, 24.0, true),
+                number("pitchShiftCents", 0.0, -2400.0, 2400.0, true),
+                number("consonantScale", 1.0, 0.2, 3.0, false),
+                number("tempo", 120.0, 10.0, 600.0, false),
+                number("timeSignatureNumerator", 4.0, 1.0, 32.0, true),
+                number("timeSignatureDenominator", 4.0, 1.0, 32.0, true),
+                combo_box(
+                    "keySignature",
+                    &[
+                        "auto", "C", "G", "D", "A", "E", "B", "F#", "C#", "F", "Bb", "Eb", "Ab",
+                        "Db", "Gb", "Cb",
+                    ],
+                ),
+            ],
+            note_properties: vec![
+                number("consonantScale", 1.0, 0.2, 3.0, false),
+                combo_box("dynamic", &["", "pp", "p", "mp", "mf", "f", "ff"]),
+                check_box("accent"),
+                check_box("staccato"),
+                combo_box("hairpin", &["", "crescendo", "decrescendo"]),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct NumberPropertyOverride {
+    pub default: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+// NOTE: プロパティ名の表記揺れ（_・空白・-、大文字小文字）は区別しない
+pub fn normalize_property_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| !matches!(c, '_' | ' ' | '-'))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+impl PropertySchema {
+    // NOTE: 音源はパートの数値プロパティの既定値・範囲を変えられる
+    pub fn for_voice(
+        overrides: &std::collections::HashMap<String, NumberPropertyOverride>,
+    ) -> Self {
+        let mut schema = Self::default();
+        for property in &mut schema.part_properties {
+            let PropertyKind::Number {
+                default, min, max, ..
+            } = &mut property.kind
+            else {
+                continue;
+            };
+            let key = normalize_property_key(&property.name);
+            let Some((_, property_override)) = overrides
+                .iter()
+                .find(|(name, _)| normalize_property_key(name) == key)
+            else {
+                continue;
+            };
+            let finite = |value: Option<f64>| value.filter(|value| value.is_finite());
+            *min = finite(property_override.min).unwrap_or(*min);
+            *max = finite(property_override.max).unwrap_or(*max).max(*min);
+            *default = finite(property_override.default)
+                .unwrap_or(*default)
+                .clamp(*min, *max);
+        }
+        schema
+    }
+
+    // NOTE: 音源を選ぶプロパティ。パートでは段（タイミング・f0・波形）ごとに、ノートではノートごとに切り替える。空ならパートの音源で歌う
+    pub fn with_voice_options(mut self, voice_ids: &[&str]) -> Self {
+        let options = std::iter::once("")
+            .chain(voice_ids.iter().copied())
+            .collect::<Vec<_>>();
+        for name in ["timingVoice", "f0Voice", "waveformVoice"] {
+            self.part_properties.push(combo_box(name, &options));
+        }
+        self.note_properties.push(combo_box("voice", &options));
+        self
+    }
+
+    pub fn resolve_part(
+        &self,
+        values: &std::collections::HashMap<String, serde_json::Value>,
+    ) -> PropertyValues {
+        PropertyValues::resolve(&self.part_properties, values)
+    }
+
+    pub fn resolve_note(
+        &self,
+        values: &std::collections::HashMap<String, serde_json::Value>,
+    ) -> PropertyValues {
+        PropertyValues::resolve(&self.note_properties, values)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PropertyValues {
+    values: std::collections::HashMap<String, PropertyValue>,
+}
+
+impl PropertyValues {
+    // NOTE: 型の合わない値や範囲外の値は既定値・範囲に収める。キーの表記揺れは正規化して吸収する
+    fn resolve(
+        definitions: &[PropertyDefinition],
+        values: &std::collections::HashMap<String, serde_json::Value>,
+    ) -> Self {
+        let values = definitions
+            .iter()
+            .map(|definition| {
+                let key = normalize_property_key(&definition.name);
+                let raw = values
+                    .iter()
+                    .find(|(name, value)| !value.is_null() && normalize_property_key(name) == key)
+                    .map(|(_, value)| value);
+                (
+                    definition.name.clone(),
+                    resolve_value(&definition.kind, raw),
+                )
+            })
+            .collect();
+        Self { values }
+    }
+
+    pub fn number(&self, name: &str) -> Option<f64> {
+        match self.values.get(name) {
+            Some(PropertyValue::Number(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.values.get(name) {
+            Some(PropertyValue::Text(value)) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.values.get(name) {
+            Some(PropertyValue::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+fn resolve_value(kind: &PropertyKind, raw: Option<&serde_json::Value>) -> PropertyValue {
+    match kind {
+        PropertyKind::Number {
+            default,
+            min,
+            max,
+            is_integer,
+        } => {
+            let value = raw
+                .and_then(|value| match value {
+                    serde_json::Value::Number(n) => n.as_f64(),
+                    serde_json::Value::String(s) => s.trim().parse::<f64>().ok(),
+                    _ => None,
+                })
+                .filter(|value| value.is_finite())
+                .map(|value| if *is_integer { value.round() } else { value })
+                .unwrap_or(*default);
+            PropertyValue::Number(value.clamp(*min, *max))
+        }
+        PropertyKind::ComboBox { default, options } => PropertyValue::Text(
+            raw.and_then(|value| value.as_str())
+                .map(str::trim)
+                .filter(|value| options.iter().any(|o| o == value))
+                .unwrap_or(default)
+                .to_string(),
+        ),
+        PropertyKind::CheckBox { default } => PropertyValue::Bool(
+            raw.and_then(|value| match value {
+                serde_json::Value::Bool(b) => Some(*b),
+                serde_json::Value::Number(n) => n.as_f64().map(|n| n != 0.0),
+                serde_json::Value::String(s) => s.trim().parse::<bool>().ok(),
+                _ => None,
+            })
+            .unwrap_or(*default),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number_kind(schema: &PropertySchema, name: &str) -> (f64, f64, f64) {
+        match &schema
+            .part_properties
+            .iter()
+            .find(|p| p.name == name)
+            .expect("must exist")
+            .kind
+        {
+            PropertyKind::Number {
+                default, min, max, ..
+            } => (*default, *min, *max),
+            kind => panic!("{name} is not a number: {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn voice_overrides_defaults_and_ranges() {
+        let overrides =
+            toml::from_str::<std::collections::HashMap<String, NumberPropertyOverride>>(
+                r#"
+style_shift = { default = 30, min = -6, max = 6 }
+waveformStyleShift = { default = -2 }
+pitchShiftCents = { min = 100, max = -100 }
+"#,
+            )
+            .expect("must parse");
+        let schema = PropertySchema::for_voice(&overrides);
+        assert_eq!(number_kind(&schema, "styleShift"), (6.0, -6.0, 6.0));
+        assert_eq!(
+            number_kind(&schema, "waveformStyleShift"),
+            (-2.0, -24.0, 24.0)
+        );
+        assert_eq!(
+            number_kind(&schema, "pitchShiftCents"),
+            (100.0, 100.0, 100.0)
+        );
+        assert_eq!(number_kind(&schema, "tempo"), (120.0, 10.0, 600.0));
+        assert_eq!(
+            schema.note_properties,
+            PropertySchema::default().note_properties
+        );
+    }
+
+    #[test]
+    fn unspecified_part_properties_use_voice_defaults() {
+        let overrides = [
+            (
+                "styleShift".to_string(),
+                NumberPropertyOverride {
+                    default: Some(3.0),
+                    ..Default::default()
+                },
+            ),
+            (
+                "waveformStyleShift".to_string(),
+                NumberPropertyOverride {
+                    default: Some(-3.0),
+                    max: Some(6.0),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let values = PropertySchema::for_voice(&overrides).resolve_part(
+            &serde_json::from_str(r#"{ "waveform_style_shift": 12.0 }"#).expect("must parse"),
+        );
+        assert_eq!(values.number("styleShift"), Some(3.0));
+        assert_eq!(values.number("waveformStyleShift"), Some(6.0));
+        assert_eq!(values.number("pitchShiftCents"), Some(0.0));
+    }
+
+    #[test]
+    fn resolves_values_by_type() {
+        let schema = PropertySchema::default();
+        let part = schema.resolve_part(
+            &serde_json::from_str(
+                r#"{
+                    "StyleShift": 2.6,
+                    "pitch shift cents": "12.4",
+                    "consonantScale": 10,
+                    "keySignature": "Zb",
+                    "tempo": null
+                }"#,
+            )
+            .expect("must parse"),
+        );
+        assert_eq!(part.number("styleShift"), Some(3.0));
+        assert_eq!(part.number("pitchShiftCents"), Some(12.0));
+        assert_eq!(part.number("consonantScale"), Some(3.0));
+        assert_eq!(part.number("tempo"), Some(120.0));
+        assert_eq!(part.text("keySignature"), Some("auto"));
+        assert_eq!(part.number("unknown"), None);
+
+        let note = schema.resolve_note(
+            &serde_json::from_str(r#"{ "dynamic": "mf", "accent": 1, "staccato": "maybe" }"#)
+                .expect("must parse"),
+        );
+        assert_eq!(note.text("dynamic"), Some("mf"));
+        assert_eq!(note.bool("accent"), Some(true));
+        assert_eq!(note.bool("staccato"), Some(false));
+        assert_eq!(note.text("hairpin"), Some(""));
+        assert_eq!(note.number("consonantScale"), Some(1.0));
+    }
+
+    #[test]
+    fn voice_options_accept_only_known_voices() {
+        let schema = PropertySchema::default().with_voice_options(&["a", "b/c"]);
+        let part = schema.resolve_part(
+            &serde_json::from_str(r#"{ "f0Voice": "b/c", "waveformVoice": "d" }"#)
+                .expect("must parse"),
+        );
+        assert_eq!(part.text("timingVoice"), Some(""));
+        assert_eq!(part.text("f0Voice"), Some("b/c"));
+        assert_eq!(part.text("waveformVoice"), Some(""));
+        let note =
+            schema.resolve_note(&serde_json::from_str(r#"{ "voice": "a" }"#).expect("must parse"));
+        assert_eq!(note.text("voice"), Some("a"));
+    }
+
+    #[test]
+    fn schema_json() {
+        let json = serde_json::to_value(PropertySchema::default()).expect("must serialize");
+        assert_eq!(
+            json["partProperties"][0],
+            serde_json::json!({
+                "name": "styleShift",
+                "type": "number",
+                "default": 0.0,
+                "min": -24.0,
+                "max": 24.0,
+                "isInteger": true
+            })
+        );
+        assert_eq!(
+            json["noteProperties"][1],
+            serde_json::json!({
+                "name": "dynamic",
+                "type": "comboBox",
+                "default": "",
+                "options": ["", "pp", "p", "mp", "mf", "f", "ff"]
+            })
+        );
+        assert_eq!(
+            json["noteProperties"][2],
+            serde_json::json!({ "name": "accent", "type": "checkBox", "default": false })
+        );
+    }
+}
@@ -1,45 +1,418 @@
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Deserialize)]
 struct NeutrinoInfo {
+    #[serde(default)]
     speaker: NeutrinoSpeakerInfo,
+    #[serde(default)]
+    properties: std::collections::HashMap<String, crate::properties::NumberPropertyOverride>,
+    #[serde(flatten)]
+    extra: toml::Table,
 }
 
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Deserialize)]
 struct NeutrinoSpeakerInfo {
+    #[serde(default)]
     name: String,
+    #[serde(default)]
     gender: String,
+    #[serde(default)]
     language: String,
+    description: Option<String>,
+    author: Option<String>,
+    version: Option<toml::Value>,
+    license: Option<String>,
+    range: Option<PitchRangeInfo>,
+    portrait: Option<String>,
+    icon: Option<String>,
+    sample: Option<String>,
+    web: Option<String>,
+    #[serde(flatten)]
+    extra: toml::Table,
+}
+
+// NOTE: 音域は ["C3", "G4"] と { low = "C3", high = "G4" } のどちらでも書ける。音名の代わりにMIDIノート番号も使える
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum PitchRangeInfo {
+    Pair([PitchInfo; 2]),
+    Table { low: PitchInfo, high: PitchInfo },
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum PitchInfo {
+    Midi(u8),
+    Name(String),
+}
+
+impl PitchInfo {
+    fn to_midi(&self) -> anyhow::Result<u8> {
+        match self {
+            PitchInfo::Midi(pitch) if *pitch <= 127 => Ok(*pitch),
+            PitchInfo::Midi(pitch) => anyhow::bail!("Invalid pitch in info.toml: {}", pitch),
+            PitchInfo::Name(name) => crate::neutrino_score::note_name_to_midi(name)
+                .ok_or_else(|| anyhow::anyhow!("Invalid pitch in info.toml: {}", name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PitchRange {
+    pub low: u8,
+    pub high: u8,
 }
 
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VoiceSource {
     id: String,
+    #[serde(skip)]
+    path: std::path::PathBuf,
     name: String,
     description: String,
+    language: String,
+    gender: String,
+    author: Option<String>,
+    version: Option<String>,
+    license: Option<String>,
+    pitch_range: Option<PitchRange>,
+    portrait: Option<String>,
+    icon: Option<String>,
+    sample: Option<String>,
+    web: Option<String>,
+    // NOTE: 知らない項目もTuneLab側で使えるようにそのまま残す
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+    #[serde(skip)]
+    property_overrides:
+        std::collections::HashMap<String, crate::properties::NumberPropertyOverride>,
+}
+
+static PROPERTIES_FILE_NAME: &str = "properties.toml";
+
+// NOTE: プロパティの既定値・範囲はinfo.tomlの[properties]か、音源のディレクトリのproperties.tomlに書ける。両方あればproperties.tomlを優先する
+fn load_property_overrides(
+    path: &std::path::Path,
+) -> anyhow::Result<std::collections::HashMap<String, crate::properties::NumberPropertyOverride>> {
+    let properties_path = path.join(PROPERTIES_FILE_NAME);
+    if !properties_path.exists() {
+        return Ok(Default::default());
+    }
+    let properties_str = std::fs::read_to_string(&properties_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", PROPERTIES_FILE_NAME, e))?;
+    toml::from_str(&properties_str)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", PROPERTIES_FILE_NAME, e))
 }
 
 impl VoiceSource {
     pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let id = path.file_name().unwrap().to_string_lossy().to_string();
+        let sidecar_overrides = load_property_overrides(path)?;
         let info_path = path.join("info.toml");
         if info_path.exists() {
             let info_str = std::fs::read_to_string(&info_path)
                 .map_err(|e| anyhow::anyhow!("Failed to read info.toml: {}", e))?;
             let info: NeutrinoInfo = toml::from_str(&info_str)
                 .map_err(|e| anyhow::anyhow!("Failed to parse info.toml: {}", e))?;
+            let speaker = info.speaker;
+
+            let pitch_range = match &speaker.range {
+                Some(PitchRangeInfo::Pair([low, high]))
+                | Some(PitchRangeInfo::Table { low, high }) => {
+                    let (low, high) = (low.to_midi()?, high.to_midi()?);
+                    Some(PitchRange {
+                        low: low.min(high),
+                        high: low.max(high),
+                    })
+                }
+                None => None,
+            };
+            let resolve_path = |file: &Option<String>| {
+                file.as_ref()
+                    .map(|file| path.join(file).to_string_lossy().to_string())
+            };
+            let mut extra = serde_json::Map::new();
+            for (key, value) in info.extra.into_iter().chain(speaker.extra) {
+                extra.insert(key, serde_json::to_value(value)?);
+            }
 
             Ok(Self {
-                id: path.file_name().unwrap().to_string_lossy().to_string(),
-                name: info.speaker.name,
-                description: format!(
-                    "Gender={}, Language={}",
-                    info.speaker.gender, info.speaker.language
-                ),
+                id,
+                path: path.to_path_buf(),
+                name: if speaker.name.is_empty() {
+                    path.file_name().unwrap().to_string_lossy().to_string()
+                } else {
+                    speaker.name
+                },
+                description: speaker.description.unwrap_or_else(|| {
+                    format!("Gender={}, Language={}", speaker.gender, speaker.language)
+                }),
+                language: speaker.language,
+                gender: speaker.gender,
+                author: speaker.author,
+                version: speaker.version.map(|version| match version {
+                    toml::Value::String(version) => version,
+                    version => version.to_string(),
+                }),
+                license: speaker.license,
+                pitch_range,
+                portrait: resolve_path(&speaker.portrait),
+                icon: resolve_path(&speaker.icon),
+                sample: resolve_path(&speaker.sample),
+                web: speaker.web,
+                extra,
+                property_overrides: info
+                    .properties
+                    .into_iter()
+                    .chain(sidecar_overrides)
+                    .collect(),
             })
         } else {
             Ok(Self {
-                id: path.file_name().unwrap().to_string_lossy().to_string(),
-                name: path.file_name().unwrap().to_string_lossy().to_string(),
+                id: id.clone(),
+                path: path.to_path_buf(),
+                name: id,
                 description: "No info.toml found".to_string(),
+                property_overrides: sidecar_overrides,
+                ..Default::default()
             })
         }
     }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn property_schema(&self) -> crate::properties::PropertySchema {
+        crate::properties::PropertySchema::for_voice(&self.property_overrides)
+    }
+}
+
+static MODEL_FILE_EXTENSIONS: &[&str] = &["bin", "onnx"];
+const MAX_SCAN_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceScan {
+    pub voices: Vec<VoiceSource>,
+    pub errors: Vec<VoiceLoadError>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceLoadError {
+    pub path: String,
+    pub message: String,
+}
+
+impl VoiceScan {
+    pub fn find(&self, voice_id: &str) -> Option<&VoiceSource> {
+        self.voices.iter().find(|voice| voice.id == voice_id)
+    }
+
+    fn push_error(&mut self, path: &std::path::Path, message: impl std::fmt::Display) {
+        self.errors.push(VoiceLoadError {
+            path: path.to_string_lossy().to_string(),
+            message: message.to_string(),
+        });
+    }
+}
+
+// NOTE: モデルファイルを直接含むディレクトリを音源とみなす
+fn is_model_directory(path: &std::path::Path) -> bool {
+    std::fs::read_dir(path).is_ok_and(|entries| {
+        entries.filter_map(Result::ok).any(|entry| {
+            entry.file_type().is_ok_and(|t| t.is_file())
+                && entry
+                    .path()
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| {
+                        MODEL_FILE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str())
+                    })
+        })
+    })
+}
+
+fn sorted_subdirectories(path: &std::path::Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let mut directories = std::fs::read_dir(path)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    directories.sort();
+    Ok(directories)
+}
+
+// NOTE: 音源IDはモデルのルートからの相対パス。ルートが複数あって重複した場合は先に見つかったものを使う
+pub fn scan_model_roots(roots: &[std::path::PathBuf]) -> VoiceScan {
+    let mut scan = VoiceScan::default();
+    for root in roots {
+        match sorted_subdirectories(root) {
+            Ok(directories) => {
+                for directory in directories {
+                    let found = scan.voices.len() + scan.errors.len();
+                    scan_directory(root, &directory, 1, &mut scan);
+                    if scan.voices.len() + scan.errors.len() == found {
+                        scan.push_error(&directory, "No NEUTRINO model found");
+                    }
+                }
+            }
+            Err(e) => scan.push_error(root, format!("Failed to read model directory: {}", e)),
+        }
+    }
+    scan
+}
+
+fn scan_directory(
+    root: &std::path::Path,
+    directory: &std::path::Path,
+    depth: usize,
+    scan: &mut VoiceScan,
+) {
+    if is_model_directory(directory) {
+        let id = directory
+            .strip_prefix(root)
+            .unwrap_or(directory)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        match VoiceSource::load(directory) {
+            Ok(_) if scan.find(&id).is_some() => scan.push_error(
+                directory,
+                format!(
+                    "Duplicate voice id {} (already loaded from {})",
+                    id,
+                    scan.find(&id).unwrap().path.display()
+                ),
+            ),
+            Ok(voice) => scan.voices.push(VoiceSource { id, ..voice }),
+            Err(e) => scan.push_error(directory, e),
+        }
+        return;
+    }
+    if directory.join("info.toml").exists() {
+        scan.push_error(directory, "info.toml found but no model files");
+        return;
+    }
+    if depth >= MAX_SCAN_DEPTH {
+        return;
+    }
+    match sorted_subdirectories(directory) {
+        Ok(subdirectories) => {
+            for subdirectory in subdirectories {
+                scan_directory(root, &subdirectory, depth + 1, scan);
+            }
+        }
+        Err(e) => scan.push_error(directory, format!("Failed to read directory: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_rich_metadata() {
+        let directory = tempfile::tempdir().expect("must create");
+        let voice_path = directory.path().join("VOICE");
+        std::fs::create_dir(&voice_path).expect("must create");
+        std::fs::write(
+            voice_path.join("info.toml"),
+            r#"
+[speaker]
+name = "Voice"
+gender = "female"
+language = "ja"
+author = "Author"
+version = 1.5
+license = "CC BY 4.0"
+range = ["A3", "E5"]
+portrait = "portrait.png"
+web = "https://example.com/"
+character = "cat"
+
+[credits]
+illustrator = "Illustrator"
+"#,
+        )
+        .expect("must write");
+
+        let voice = VoiceSource::load(&voice_path).expect("must load");
+        let json = serde_json::to_value(&voice).expect("must serialize");
+        assert_eq!(json["id"], "VOICE");
+        assert_eq!(json["name"], "Voice");
+        assert_eq!(json["description"], "Gender=female, Language=ja");
+        assert_eq!(json["language"], "ja");
+        assert_eq!(json["author"], "Author");
+        assert_eq!(json["version"], "1.5");
+        assert_eq!(json["license"], "CC BY 4.0");
+        assert_eq!(
+            json["pitchRange"],
+            serde_json::json!({ "low": 57, "high": 76 })
+        );
+        assert_eq!(
+            json["portrait"],
+            voice_path.join("portrait.png").to_string_lossy().as_ref()
+        );
+        assert_eq!(json["icon"], serde_json::Value::Null);
+        assert_eq!(json["web"], "https://example.com/");
+        assert_eq!(json["character"], "cat");
+        assert_eq!(json["credits"]["illustrator"], "Illustrator");
+    }
+
+    #[test]
+    fn pitch_range_accepts_tables_and_midi_numbers() {
+        let directory = tempfile::tempdir().expect("must create");
+        std::fs::write(
+            directory.path().join("info.toml"),
+            "[speaker]\nrange = { low = 72, high = \"C3\" }\n",
+        )
+        .expect("must write");
+        let voice = VoiceSource::load(directory.path()).expect("must load");
+        assert_eq!(voice.pitch_range, Some(PitchRange { low: 48, high: 72 }));
+
+        std::fs::write(
+            directory.path().join("info.toml"),
+            "[speaker]\nrange = [\"C3\", \"X9\"]\n",
+        )
+        .expect("must write");
+        assert!(VoiceSource::load(directory.path()).is_err());
+    }
+
+    #[test]
+    fn sidecar_properties_override_info_toml() {
+        let directory = tempfile::tempdir().expect("must create");
+        std::fs::write(
+            directory.path().join("info.toml"),
+            "[speaker]\nname = \"Voice\"\n\n[properties]\nstyleShift = { default = 2 }\npitchShiftCents = { default = 50 }\n",
+        )
+        .expect("must write");
+        std::fs::write(
+            directory.path().join("properties.toml"),
+            "[styleShift]\ndefault = -3\nmin = -5\n",
+        )
+        .expect("must write");
+        let schema = VoiceSource::load(directory.path())
+            .expect("must load")
+            .property_schema();
+        let values = schema.resolve_part(&Default::default());
+        assert_eq!(values.number("styleShift"), Some(-3.0));
+        assert_eq!(values.number("waveformStyleShift"), Some(0.0));
+        assert_eq!(values.number("pitchShiftCents"), Some(50.0));
+        let values = schema.resolve_part(
+            &[("styleShift".to_string(), serde_json::json!(-10))]
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(values.number("styleShift"), Some(-5.0));
+    }
 }
@@ -1,6 +1,4 @@
-use wana_kana::ConvertJapanese;
-
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[expect(dead_code)]
 pub struct SynthesisTaskPayload {
@@ -8,20 +6,79 @@ pub struct SynthesisTaskPayload {
     pub start_time: f64,
     pub end_time: f64,
     pub duration: f64,
+    pub part_properties: std::collections::HashMap<String, serde_json::Value>,
     #[serde(default)]
-    pub style_shift: f64,
+    pub tempos: Vec<TempoPayload>,
     #[serde(default)]
-    pub waveform_style_shift: f64,
+    pub time_signatures: Vec<TimeSignaturePayload>,
     #[serde(default)]
-    pub pitch_shift_cents: f64,
-    pub part_properties: std::collections::HashMap<String, serde_json::Value>,
+    pub key_signatures: Vec<KeySignaturePayload>,
     pub notes: Vec<SynthesisNotePayload>,
     pub pitch: PitchPayload,
 }
 
-#[derive(Debug, serde::Deserialize)]
+static KEY_SIGNATURE_NAMES: &[&str] = &[
+    "Cb", "Gb", "Db", "Ab", "Eb", "Bb", "F", "C", "G", "D", "A", "E", "B", "F#", "C#",
+];
+
+impl SynthesisTaskPayload {
+    // NOTE: テンポ・拍子・調号の指定がなければパートプロパティから補う。調号がautoならノートから推定させる
+    pub fn apply_part_properties(&mut self, values: &crate::properties::PropertyValues) {
+        if self.tempos.is_empty() {
+            if let Some(tempo) = values.number("tempo") {
+                self.tempos.push(TempoPayload { time: 0.0, tempo });
+            }
+        }
+        if self.time_signatures.is_empty() {
+            if let (Some(numerator), Some(denominator)) = (
+                values.number("timeSignatureNumerator"),
+                values.number("timeSignatureDenominator"),
+            ) {
+                self.time_signatures.push(TimeSignaturePayload {
+                    time: 0.0,
+                    numerator: numerator as u8,
+                    denominator: denominator as u8,
+                });
+            }
+        }
+        if self.key_signatures.is_empty() {
+            if let Some(index) = values
+                .text("keySignature")
+                .and_then(|name| KEY_SIGNATURE_NAMES.iter().position(|n| *n == name))
+            {
+                self.key_signatures.push(KeySignaturePayload {
+                    time: 0.0,
+                    fifths: index as i8 - 7,
+                });
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TempoPayload {
+    pub time: f64,
+    pub tempo: f64,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeSignaturePayload {
+    pub time: f64,
+    pub numerator: u8,
+    pub denominator: u8,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeySignaturePayload {
+    pub time: f64,
+    pub fifths: i8,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-#[expect(dead_code)]
 pub struct SynthesisNotePayload {
     pub start_time: f64,
     pub end_time: f64,
@@ -33,7 +90,7 @@ pub struct SynthesisNotePayload {
     pub phonemes: Vec<SynthesisPhonemePayload>,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SynthesisPhonemePayload {
     pub symbol: String,
@@ -41,7 +98,7 @@ pub struct SynthesisPhonemePayload {
     pub end_time: f64,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PitchPayload {
     pub times: Vec<f64>,
@@ -58,6 +115,7 @@ pub struct SynthesisResponse {
     pub pitch_times: Vec<f64>,
     pub pitch_values: Vec<f64>,
     pub note_phonemes: Vec<NotePhonemes>,
+    pub timing_adjustments: Vec<TimingAdjustment>,
     pub note_count: usize,
     pub phoneme_count: usize,
     pub property_count: usize,
@@ -78,6 +136,27 @@ pub struct NotePhonemes {
     pub phonemes: Vec<SynthesizedPhoneme>,
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingAdjustment {
+    pub note_index: Option<usize>,
+    pub phoneme_index: usize,
+    pub symbol: String,
+    pub kinds: Vec<crate::timing::AdjustmentKind>,
+    pub original_start_time: f64,
+    pub original_end_time: f64,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhonemePreview {
+    pub lyric: String,
+    pub phonemes: Vec<String>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct LooseF64(pub f64);
 
@@ -143,223 +222,439 @@ fn normalize_loose_f64(value: f64) -> f64 {
     }
 }
 
-static PHONEMES: &[&str] = &[
-    "a", "i", "u", "e", "o", "ky", "k", "sh", "s", "ch", "t", "ts", "n", "ny", "h", "hy", "m", "f",
-    "y", "my", "r", "ry", "w", "gy", "j", "g", "by", "z", "py", "d", "v", "b", "p", "dy", "N",
-    "cl", "br", "pau", "sil",
-];
-pub fn mora_to_phonemes(mora: &str) -> anyhow::Result<Vec<String>> {
-    Ok(match mora.to_katakana().as_str() {
-        "ア" => vec!["a".to_string()],
-        "イ" => vec!["i".to_string()],
-        "ウ" => vec!["u".to_string()],
-        "エ" => vec!["e".to_string()],
-        "オ" => vec!["o".to_string()],
-        "キャ" => vec!["ky".to_string(), "a".to_string()],
-        "キュ" => vec!["ky".to_string(), "u".to_string()],
-        "キェ" => vec!["ky".to_string(), "e".to_string()],
-        "キョ" => vec!["ky".to_string(), "o".to_string()],
-        "カ" => vec!["k".to_string(), "a".to_string()],
-        "キ" => vec!["k".to_string(), "i".to_string()],
-        "ク" => vec!["k".to_string(), "u".to_string()],
-        "ケ" => vec!["k".to_string(), "e".to_string()],
-        "コ" => vec!["k".to_string(), "o".to_string()],
-        "シャ" => vec!["sh".to_string(), "a".to_string()],
-        "スィ" => vec!["s".to_string(), "i".to_string()],
-        "シュ" => vec!["sh".to_string(), "u".to_string()],
-        "シェ" => vec!["sh".to_string(), "e".to_string()],
-        "ショ" => vec!["sh".to_string(), "o".to_string()],
-        "サ" => vec!["s".to_string(), "a".to_string()],
-        "シ" => vec!["sh".to_string(), "i".to_string()],
-        "ス" => vec!["s".to_string(), "u".to_string()],
-        "セ" => vec!["s".to_string(), "e".to_string()],
-        "ソ" => vec!["s".to_string(), "o".to_string()],
-        "チャ" => vec!["ch".to_string(), "a".to_string()],
-        "チュ" => vec!["ch".to_string(), "u".to_string()],
-        "チェ" => vec!["ch".to_string(), "e".to_string()],
-        "チョ" => vec!["ch".to_string(), "o".to_string()],
-        "タ" => vec!["t".to_string(), "a".to_string()],
-        "チ" => vec!["ch".to_string(), "i".to_string()],
-        "ツ" => vec!["ts".to_string(), "u".to_string()],
-        "テ" => vec!["t".to_string(), "e".to_string()],
-        "ト" => vec!["t".to_string(), "o".to_string()],
-        "ツァ" => vec!["ts".to_string(), "a".to_string()],
-        "ツィ" => vec!["ts".to_string(), "i".to_string()],
-        "ツェ" => vec!["ts".to_string(), "e".to_string()],
-        "ツォ" => vec!["ts".to_string(), "o".to_string()],
-        "ナ" => vec!["n".to_string(), "a".to_string()],
-        "ニ" => vec!["n".to_string(), "i".to_string()],
-        "ヌ" => vec!["n".to_string(), "u".to_string()],
-        "ネ" => vec!["n".to_string(), "e".to_string()],
-        "ノ" => vec!["n".to_string(), "o".to_string()],
-        "ニャ" => vec!["ny".to_string(), "a".to_string()],
-        "ニュ" => vec!["ny".to_string(), "u".to_string()],
-        "ニェ" => vec!["ny".to_string(), "e".to_string()],
-        "ニョ" => vec!["ny".to_string(), "o".to_string()],
-        "ハ" => vec!["h".to_string(), "a".to_string()],
-        "ヒ" => vec!["h".to_string(), "i".to_string()],
-        "フ" => vec!["h".to_string(), "u".to_string()],
-        "ヘ" => vec!["h".to_string(), "e".to_string()],
-        "ホ" => vec!["h".to_string(), "o".to_string()],
-        "ヒャ" => vec!["hy".to_string(), "a".to_string()],
-        "ヒュ" => vec!["hy".to_string(), "u".to_string()],
-        "ヒェ" => vec!["hy".to_string(), "e".to_string()],
-        "ヒョ" => vec!["hy".to_string(), "o".to_string()],
-        "マ" => vec!["m".to_string(), "a".to_string()],
-        "ミ" => vec!["m".to_string(), "i".to_string()],
-        "ム" => vec!["m".to_string(), "u".to_string()],
-        "メ" => vec!["m".to_string(), "e".to_string()],
-        "モ" => vec!["m".to_string(), "o".to_string()],
-        "ファ" => vec!["f".to_string(), "a".to_string()],
-        "フィ" => vec!["f".to_string(), "i".to_string()],
-        "フェ" => vec!["f".to_string(), "e".to_string()],
-        "フォ" => vec!["f".to_string(), "o".to_string()],
-        "ヤ" => vec!["y".to_string(), "a".to_string()],
-        "ユ" => vec!["y".to_string(), "u".to_string()],
-        "イェ" => vec!["y".to_string(), "e".to_string()],
-        "ヨ" => vec!["y".to_string(), "o".to_string()],
-        "ミャ" => vec!["my".to_string(), "a".to_string()],
-        "ミュ" => vec!["my".to_string(), "u".to_string()],
-        "ミェ" => vec!["my".to_string(), "e".to_string()],
-        "ミョ" => vec!["my".to_string(), "o".to_string()],
-        "ラ" => vec!["r".to_string(), "a".to_string()],
-        "リ" => vec!["r".to_string(), "i".to_string()],
-        "ル" => vec!["r".to_string(), "u".to_string()],
-        "レ" => vec!["r".to_string(), "e".to_string()],
-        "ロ" => vec!["r".to_string(), "o".to_string()],
-        "リャ" => vec!["ry".to_string(), "a".to_string()],
-        "リュ" => vec!["ry".to_string(), "u".to_string()],
-        "リェ" => vec!["ry".to_string(), "e".to_string()],
-        "リョ" => vec!["ry".to_string(), "o".to_string()],
-        "ワ" => vec!["w".to_string(), "a".to_string()],
-        "ヲ" => vec!["o".to_string()],
-        "ギャ" => vec!["gy".to_string(), "a".to_string()],
-        "ギュ" => vec!["gy".to_string(), "u".to_string()],
-        "ギェ" => vec!["gy".to_string(), "e".to_string()],
-        "ギョ" => vec!["gy".to_string(), "o".to_string()],
-        "ジャ" => vec!["j".to_string(), "a".to_string()],
-        "ジュ" => vec!["j".to_string(), "u".to_string()],
-        "ジェ" => vec!["j".to_string(), "e".to_string()],
-        "ジョ" => vec!["j".to_string(), "o".to_string()],
-        "ガ" => vec!["g".to_string(), "a".to_string()],
-        "ギ" => vec!["g".to_string(), "i".to_string()],
-        "グ" => vec!["g".to_string(), "u".to_string()],
-        "ゲ" => vec!["g".to_string(), "e".to_string()],
-        "ゴ" => vec!["g".to_string(), "o".to_string()],
-        "ビャ" => vec!["by".to_string(), "a".to_string()],
-        "ビュ" => vec!["by".to_string(), "u".to_string()],
-        "ビェ" => vec!["by".to_string(), "e".to_string()],
-        "ビョ" => vec!["by".to_string(), "o".to_string()],
-        "ザ" => vec!["z".to_string(), "a".to_string()],
-        "ジ" => vec!["j".to_string(), "i".to_string()],
-        "ズ" => vec!["z".to_string(), "u".to_string()],
-        "ゼ" => vec!["z".to_string(), "e".to_string()],
-        "ゾ" => vec!["z".to_string(), "o".to_string()],
-        "ピャ" => vec!["py".to_string(), "a".to_string()],
-        "ピュ" => vec!["py".to_string(), "u".to_string()],
-        "ピェ" => vec!["py".to_string(), "e".to_string()],
-        "ピョ" => vec!["py".to_string(), "o".to_string()],
-        "ダ" => vec!["d".to_string(), "a".to_string()],
-        "ヂ" => vec!["j".to_string(), "i".to_string()],
-        "ヅ" => vec!["z".to_string(), "u".to_string()],
-        "デ" => vec!["d".to_string(), "e".to_string()],
-        "ド" => vec!["d".to_string(), "o".to_string()],
-        "ヴぁ" => vec!["v".to_string(), "a".to_string()],
-        "ヴぃ" => vec!["v".to_string(), "i".to_string()],
-        "ヴ" => vec!["v".to_string(), "u".to_string()],
-        "ヴぇ" => vec!["v".to_string(), "e".to_string()],
-        "ヴぉ" => vec!["v".to_string(), "o".to_string()],
-        "バ" => vec!["b".to_string(), "a".to_string()],
-        "ビ" => vec!["b".to_string(), "i".to_string()],
-        "ブ" => vec!["b".to_string(), "u".to_string()],
-        "ベ" => vec!["b".to_string(), "e".to_string()],
-        "ボ" => vec!["b".to_string(), "o".to_string()],
-        "ウィ" => vec!["w".to_string(), "i".to_string()],
-        "ウェ" => vec!["w".to_string(), "e".to_string()],
-        "ウォ" => vec!["w".to_string(), "o".to_string()],
-        "パ" => vec!["p".to_string(), "a".to_string()],
-        "ピ" => vec!["p".to_string(), "i".to_string()],
-        "プ" => vec!["p".to_string(), "u".to_string()],
-        "ペ" => vec!["p".to_string(), "e".to_string()],
-        "ポ" => vec!["p".to_string(), "o".to_string()],
-        "ディ" => vec!["d".to_string(), "i".to_string()],
-        "デュ" => vec!["dy".to_string(), "u".to_string()],
-        "トゥ" => vec!["t".to_string(), "u".to_string()],
-        "ドゥ" => vec!["d".to_string(), "u".to_string()],
-        "ン" => vec!["N".to_string()],
-        "ッ" => vec!["cl".to_string()],
-        "ズィ" => vec!["z".to_string(), "i".to_string()],
-        _ if PHONEMES.contains(&mora.to_katakana().as_str()) => {
-            vec![mora.to_katakana().to_lowercase()]
+pub static CONTINUATION_LYRICS: &[&str] = &["-", "ー", "+"];
+
+// NOTE: 複数モーラの歌詞に「+」のノートが続く場合はモーラを1つずつ割り当て、余ったモーラは最後のノートにまとめる
+fn distribute_lyrics(
+    notes: &[SynthesisNotePayload],
+    phonemizer: &dyn crate::phonemizer::Phonemizer,
+) -> anyhow::Result<Vec<Option<String>>> {
+    distribute_lyric_texts(
+        &notes
+            .iter()
+            .map(|note| note.phonemes.is_empty().then_some(note.lyric.as_str()))
+            .collect::<Vec<_>>(),
+        phonemizer,
+    )
+}
+
+// NOTE: Noneは音素を直接指定したノート
+fn distribute_lyric_texts(
+    lyrics: &[Option<&str>],
+    phonemizer: &dyn crate::phonemizer::Phonemizer,
+) -> anyhow::Result<Vec<Option<String>>> {
+    let is_split_target = |lyric: &Option<&str>| lyric.is_some_and(|lyric| lyric.trim() == "+");
+    let mut distributed = vec![None; lyrics.len()];
+    let mut i = 0;
+    while i < lyrics.len() {
+        let Some(lyric) = lyrics[i].filter(|lyric| !CONTINUATION_LYRICS.contains(&lyric.trim()))
+        else {
+            i += 1;
+            continue;
+        };
+        let followers = lyrics[i + 1..]
+            .iter()
+            .take_while(|lyric| is_split_target(lyric))
+            .count();
+        if followers == 0 {
+            distributed[i] = Some(lyric.to_string());
+            i += 1;
+            continue;
+        }
+        let syllables = phonemizer.split_syllables(lyric)?;
+        let count = syllables.len().clamp(1, followers + 1);
+        for j in 0..count - 1 {
+            distributed[i + j] = Some(syllables[j].clone());
+        }
+        distributed[i + count - 1] = Some(syllables[count - 1..].concat());
+        i += count;
+    }
+    Ok(distributed)
+}
+
+// NOTE: 歌詞の並びを続けて歌うノートとみなし、合成と同じく「+」へモーラを割り当て、伸ばし記号には直前の母音を引き継ぐ。
+//       直前の歌詞がなければ伸ばし記号の音素は空にする
+pub fn preview_lyrics(
+    lyrics: &[String],
+    phonemizer: &dyn crate::phonemizer::Phonemizer,
+) -> Vec<PhonemePreview> {
+    let distributed = distribute_lyric_texts(
+        &lyrics
+            .iter()
+            .map(|lyric| Some(lyric.as_str()))
+            .collect::<Vec<_>>(),
+        phonemizer,
+    )
+    .unwrap_or_else(|_| {
+        lyrics
+            .iter()
+            .map(|lyric| (!CONTINUATION_LYRICS.contains(&lyric.trim())).then(|| lyric.clone()))
+            .collect()
+    });
+    let mut previews: Vec<PhonemePreview> = Vec::with_capacity(lyrics.len());
+    for (lyric, distributed) in lyrics.iter().zip(distributed) {
+        let result = match distributed {
+            Some(distributed) => phonemizer.phonemize(&distributed),
+            None => Ok(previews
+                .last()
+                .and_then(|preview| {
+                    preview
+                        .phonemes
+                        .iter()
+                        .rev()
+                        .find(|p| phonemizer.is_vowel(p))
+                })
+                .cloned()
+                .into_iter()
+                .collect()),
+        };
+        previews.push(match result {
+            Ok(phonemes) => PhonemePreview {
+                lyric: lyric.clone(),
+                phonemes,
+                error: None,
+            },
+            Err(e) => PhonemePreview {
+                lyric: lyric.clone(),
+                phonemes: Vec::new(),
+                error: Some(e.to_string()),
+            },
+        });
+    }
+    previews
+}
+
+fn event_at<T>(events: &[T], time: f64, event_time: impl Fn(&T) -> f64) -> Option<&T> {
+    events
+        .iter()
+        .filter(|event| event_time(event) <= time)
+        .max_by(|a, b| event_time(a).total_cmp(&event_time(b)))
+        .or_else(|| events.first())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreOptions {
+    pub min_rest_duration_ns: u64,
+    pub breath: Option<BreathOptions>,
+}
+
+impl Default for ScoreOptions {
+    fn default() -> Self {
+        Self {
+            min_rest_duration_ns: 50_000_000,
+            breath: None,
         }
-        _ => anyhow::bail!("Unsupported mora: {}", mora),
-    })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreathOptions {
+    pub min_gap_ns: u64,
+    pub length_ns: u64,
+}
+
+// NOTE: フレーズの前の休符が十分長ければ、末尾を息継ぎ（br）にする
+fn push_rest(
+    score: &mut crate::neutrino_score::Score,
+    start_time_ns: u64,
+    end_time_ns: u64,
+    options: &ScoreOptions,
+    language: &str,
+) {
+    let breath_start_time_ns = options
+        .breath
+        .as_ref()
+        .filter(|breath| end_time_ns - start_time_ns >= breath.min_gap_ns)
+        .map(|breath| {
+            end_time_ns
+                .saturating_sub(breath.length_ns)
+                .max(start_time_ns)
+        });
+    let rest_end_time_ns = breath_start_time_ns.unwrap_or(end_time_ns);
+    for (phoneme, start_time_ns, end_time_ns) in [
+        ("pau", start_time_ns, rest_end_time_ns),
+        ("br", rest_end_time_ns, end_time_ns),
+    ] {
+        if start_time_ns >= end_time_ns {
+            continue;
+        }
+        score.notes.push(crate::neutrino_score::Note {
+            pitch: None,
+            start_time_ns,
+            length: crate::neutrino_score::NoteLength::from_32nd_triplet_note_float(
+                score.triplet_32nd_between(start_time_ns, end_time_ns),
+            ),
+            end_time_ns: Some(end_time_ns),
+            phonemes: vec![phoneme.to_string()],
+            language: Some(language.to_string()),
+            language_dependent_context: Some("p".to_string()),
+            slur: false,
+            marks: Default::default(),
+        });
+    }
 }
 
 pub fn task_notes_to_score(
     notes: &[SynthesisNotePayload],
+    tempos: &[TempoPayload],
+    time_signatures: &[TimeSignaturePayload],
+    key_signatures: &[KeySignaturePayload],
+    options: &ScoreOptions,
+    phonemizer: &dyn crate::phonemizer::Phonemizer,
 ) -> anyhow::Result<crate::neutrino_score::Score> {
     if notes.is_empty() {
         anyhow::bail!("No notes provided in synthesis task payload");
     }
-    // Synthesis task times are in seconds.
-    // Until tempo is provided by payload, use `bpm = 60000` to interpret note lengths as
-    // milliseconds.
-    let bpm = 60000.0;
-    let mut score = crate::neutrino_score::Score {
-        tempo: bpm,
-        ..Default::default()
-    };
+    let first_note_start_time = notes[0].start_time;
+    let tempos = tempos
+        .iter()
+        .filter(|t| t.tempo.is_finite() && t.tempo > 0.0)
+        .cloned()
+        .collect::<Vec<_>>();
+    let time_signatures = time_signatures
+        .iter()
+        .filter(|t| t.numerator > 0 && t.denominator > 0)
+        .cloned()
+        .collect::<Vec<_>>();
+    let initial_bpm = event_at(&tempos, first_note_start_time, |t| t.time)
+        .map_or(crate::neutrino_score::Tempo::default().tempo, |t| t.tempo);
+    let initial_time_signature_payload =
+        event_at(&time_signatures, first_note_start_time, |t| t.time);
+    let initial_time_signature = initial_time_signature_payload
+        .map(|t| crate::neutrino_score::TimeSignature {
+            start_time_ns: 0,
+            numerator: t.numerator,
+            denominator: t.denominator,
+        })
+        .unwrap_or_default();
 
     let first_pau = crate::neutrino_score::Note {
         pitch: None,
         start_time_ns: 0,
-        length: crate::neutrino_score::NoteLength::from_seconds_float(1.0, bpm),
-        language: Some("JPN".to_string()),
+        length: crate::neutrino_score::NoteLength::from_seconds_float(1.0, initial_bpm),
+        end_time_ns: None,
+        language: Some(phonemizer.language().to_string()),
         language_dependent_context: Some("p".to_string()),
+        slur: false,
+        marks: Default::default(),
         phonemes: vec!["pau".to_string()],
     };
-    let first_pau_length_ns = first_pau.length.to_nanoseconds(bpm);
-    score.notes.push(first_pau);
-    let first_note_start_time = notes[0].start_time;
-    for note in notes {
-        let phonemes: Vec<String> = if note.phonemes.is_empty() {
-            mora_to_phonemes(&note.lyric)?
-        } else {
+    let first_pau_length_ns = first_pau.length.to_nanoseconds(initial_bpm);
+    // Synthesis task times are in seconds.
+    let to_score_time_ns = |time: f64| {
+        ((time - first_note_start_time).max(0.0) * 1_000_000_000.0).round() as u64
+            + first_pau_length_ns
+    };
+
+    let mut score = crate::neutrino_score::Score {
+        tempos: std::iter::once(crate::neutrino_score::Tempo {
+            start_time_ns: 0,
+            tempo: initial_bpm,
+        })
+        .chain(
+            tempos
+                .iter()
+                .filter(|t| t.time > first_note_start_time)
+                .map(|t| crate::neutrino_score::Tempo {
+                    start_time_ns: to_score_time_ns(t.time),
+                    tempo: t.tempo,
+                }),
+        )
+        .collect(),
+        time_signatures: std::iter::once(initial_time_signature)
+            .chain(
+                time_signatures
+                    .iter()
+                    .filter(|t| t.time > first_note_start_time)
+                    .map(|t| crate::neutrino_score::TimeSignature {
+                        start_time_ns: to_score_time_ns(t.time),
+                        numerator: t.numerator,
+                        denominator: t.denominator,
+                    }),
+            )
+            .collect(),
+        ..Default::default()
+    };
+    if let Some(time_signature) = initial_time_signature_payload {
+        // NOTE: 小節線の位置を合わせるため、最初のpauの中で最初に来る小節の頭を拍子の開始位置にする
+        let to_tunelab_time_ns = |time: f64| (time.max(0.0) * 1_000_000_000.0).round() as u64;
+        let tunelab_tempo_map = crate::neutrino_score::Score {
+            tempos: tempos
+                .iter()
+                .map(|t| crate::neutrino_score::Tempo {
+                    start_time_ns: to_tunelab_time_ns(t.time),
+                    tempo: t.tempo,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let measure_length = score.time_signatures[0].measure_length_triplet_32nd();
+        let offset_in_measure = tunelab_tempo_map
+            .triplet_32nd_between(
+                to_tunelab_time_ns(time_signature.time),
+                to_tunelab_time_ns(first_note_start_time),
+            )
+            .round() as i64;
+        let first_measure_start =
+            (i32::from(first_pau.length) as i64 - offset_in_measure).rem_euclid(measure_length);
+        score.time_signatures[0].start_time_ns =
+            score.advance_triplet_32nd(0, first_measure_start as i32);
+    }
+    push_rest(
+        &mut score,
+        0,
+        first_pau_length_ns,
+        options,
+        phonemizer.language(),
+    );
+    let lyrics = distribute_lyrics(notes, phonemizer)?;
+    let note_schema = crate::properties::PropertySchema::default();
+    let note_values = notes
+        .iter()
+        .map(|note| note_schema.resolve_note(&note.properties))
+        .collect::<Vec<_>>();
+    for (i, (note, lyric)) in notes.iter().zip(&lyrics).enumerate() {
+        let slur = lyric.is_none() && CONTINUATION_LYRICS.contains(&note.lyric.trim());
+        let phonemes: Vec<String> = if !note.phonemes.is_empty() {
             note.phonemes.iter().map(|p| p.symbol.clone()).collect()
+        } else if let Some(lyric) = lyric {
+            phonemizer.phonemize(lyric)?
+        } else {
+            // NOTE: 伸ばし記号は直前のノートの母音を引き継ぐ
+            let previous_vowel = score
+                .notes
+                .last()
+                .filter(|n| !n.is_rest())
+                .and_then(|n| n.phonemes.iter().rev().find(|p| phonemizer.is_vowel(p)))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Continuation lyric \"{}\" at {} s has no preceding vowel",
+                        note.lyric,
+                        note.start_time
+                    )
+                })?;
+            vec![previous_vowel.clone()]
         };
 
-        let start_time_ns = ((note.start_time - first_note_start_time).max(0.0) * 1_000_000_000.0)
-            .round() as u64
-            + first_pau_length_ns;
+        let start_time_ns = to_score_time_ns(note.start_time);
+        let mut end_time_ns = to_score_time_ns(note.end_time.max(note.start_time));
+        // NOTE: 次のノートと重なる場合は切り詰め、閾値未満の隙間は埋め、それ以上の隙間には休符を入れる
+        let mut rest = None;
+        if let Some(next_start_time_ns) = notes.get(i + 1).map(|n| to_score_time_ns(n.start_time)) {
+            let next_start_time_ns = next_start_time_ns.max(start_time_ns);
+            let gap_ns = next_start_time_ns.saturating_sub(end_time_ns);
+            if gap_ns > 0 && gap_ns >= options.min_rest_duration_ns {
+                rest = Some((end_time_ns, next_start_time_ns));
+            } else {
+                end_time_ns = next_start_time_ns;
+            }
+        }
 
         score.notes.push(crate::neutrino_score::Note {
             pitch: Some(note.pitch.clamp(0, 127) as u8),
             start_time_ns,
-            length: crate::neutrino_score::NoteLength::from_4th_note_float(
-                (note.end_time - note.start_time).max(0.0) * (bpm / 60.0),
+            length: crate::neutrino_score::NoteLength::from_32nd_triplet_note_float(
+                score.triplet_32nd_between(start_time_ns, end_time_ns),
             ),
+            end_time_ns: Some(end_time_ns),
             phonemes,
-            language: Some("JPN".to_string()),
+            language: Some(phonemizer.language().to_string()),
             language_dependent_context: Some("0".to_string()),
+            slur,
+            marks: crate::neutrino_score::NoteMarks {
+                dynamic: note_values[i]
+                    .text("dynamic")
+                    .and_then(|dynamic| dynamic.parse().ok()),
+                accent: note_values[i].bool("accent").unwrap_or(false),
+                staccato: note_values[i].bool("staccato").unwrap_or(false),
+            },
         });
+        if let Some((rest_start_time_ns, rest_end_time_ns)) = rest {
+            push_rest(
+                &mut score,
+                rest_start_time_ns,
+                rest_end_time_ns,
+                options,
+                phonemizer.language(),
+            );
+        }
+    }
+    // NOTE: 調号の指定がなければノートから推定する
+    score.key_signatures = match event_at(key_signatures, first_note_start_time, |k| k.time) {
+        Some(initial) => std::iter::once(initial)
+            .map(|k| (0, k.fifths))
+            .chain(
+                key_signatures
+                    .iter()
+                    .filter(|k| k.time > first_note_start_time)
+                    .map(|k| (to_score_time_ns(k.time), k.fifths)),
+            )
+            .map(
+                |(start_time_ns, fifths)| crate::neutrino_score::KeySignature {
+                    start_time_ns,
+                    fifths: fifths.clamp(-7, 7),
+                },
+            )
+            .collect(),
+        None => vec![crate::neutrino_score::estimate_key_signature(&score.notes)],
+    };
+    // NOTE: 同じ松葉のプロパティが続くノートはひとつの松葉にまとめる
+    let mut previous_kind = None;
+    for (note, values) in notes.iter().zip(&note_values) {
+        let kind = match values.text("hairpin") {
+            Some("crescendo") => Some(crate::neutrino_score::HairpinKind::Crescendo),
+            Some("decrescendo") => Some(crate::neutrino_score::HairpinKind::Decrescendo),
+            _ => None,
+        };
+        match (kind, score.hairpins.last_mut()) {
+            (Some(kind), Some(last)) if previous_kind == Some(kind) => {
+                last.end_time_ns = to_score_time_ns(note.end_time);
+            }
+            (Some(kind), _) => score.hairpins.push(crate::neutrino_score::Hairpin {
+                kind,
+                start_time_ns: to_score_time_ns(note.start_time),
+                end_time_ns: to_score_time_ns(note.end_time),
+            }),
+            (None, _) => {}
+        }
+        previous_kind = kind;
     }
+    let last_pau_start_time_ns = crate::neutrino_score::compute_score_time_ranges_ns(&score)
+        .last()
+        .map_or(0, |&(_, end_time_ns)| end_time_ns);
     score.notes.push(crate::neutrino_score::Note {
         pitch: None,
-        start_time_ns: score
-            .notes
-            .last()
-            .map(|n| n.start_time_ns + n.length.to_nanoseconds(bpm))
-            .unwrap_or(0),
-        length: crate::neutrino_score::NoteLength::from_seconds_float(1.0, bpm),
+        start_time_ns: last_pau_start_time_ns,
+        length: crate::neutrino_score::NoteLength::from_seconds_float(
+            1.0,
+            score.tempo_at(last_pau_start_time_ns),
+        ),
+        end_time_ns: None,
         phonemes: vec!["pau".to_string()],
-        language: Some("JPN".to_string()),
+        language: Some(phonemizer.language().to_string()),
         language_dependent_context: Some("p".to_string()),
+        slur: false,
+        marks: Default::default(),
     });
 
     Ok(score)
 }
 
+// NOTE: 休符（最初と最後のpauと挿入した休符）以外のノートがTuneLabのノートに順に対応する
+pub fn payload_note_indices(score: &crate::neutrino_score::Score) -> Vec<Option<usize>> {
+    let mut next_index = 0;
+    score
+        .notes
+        .iter()
+        .map(|note| {
+            note.pitch.map(|_| {
+                next_index += 1;
+                next_index - 1
+            })
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct TimingLabel {
     pub start_time_ns: u64,
@@ -367,30 +662,6 @@ pub struct TimingLabel {
     pub phoneme: String,
 }
 
-pub fn parse_timing_label_file(label_file_content: &str) -> anyhow::Result<Vec<TimingLabel>> {
-    let mut labels = Vec::new();
-    for line in label_file_content.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 3 {
-            continue;
-        }
-        let start_time_100ns = parts[0]
-            .parse::<u64>()
-            .map_err(|e| anyhow::anyhow!("Failed to parse start time in label file: {}", e))?;
-        let end_time_100ns = parts[1]
-            .parse::<u64>()
-            .map_err(|e| anyhow::anyhow!("Failed to parse end time in label file: {}", e))?;
-        let phoneme = parts[2].to_string();
-        labels.push(TimingLabel {
-            start_time_ns: start_time_100ns * 100,
-            end_time_ns: end_time_100ns * 100,
-            phoneme,
-        });
-    }
-
-    Ok(labels)
-}
-
 pub fn freq_to_midi(freq: f32) -> f32 {
     69.0 + 12.0 * (freq / 440.0).log2()
 }
@@ -402,9 +673,638 @@ pub fn midi_to_freq(midi: f32) -> f32 {
 mod tests {
     use super::*;
 
+    fn note(start_time: f64, end_time: f64, lyric: &str) -> SynthesisNotePayload {
+        SynthesisNotePayload {
+            start_time,
+            end_time,
+            pitch: 60,
+            lyric: lyric.to_string(),
+            last_index: None,
+            next_index: None,
+            properties: Default::default(),
+            phonemes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn score_uses_payload_tempo_and_time_signature() {
+        let notes = [note(2.0, 2.5, "ら"), note(2.5, 3.5, "ら")];
+        let tempos = [
+            TempoPayload {
+                time: 0.0,
+                tempo: 90.0,
+            },
+            TempoPayload {
+                time: 1.0,
+                tempo: 120.0,
+            },
+        ];
+        let time_signatures = [TimeSignaturePayload {
+            time: 0.0,
+            numerator: 3,
+            denominator: 4,
+        }];
+        let score = task_notes_to_score(
+            &notes,
+            &tempos,
+            &time_signatures,
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(score.tempos.len(), 1);
+        assert_eq!(score.tempos[0].tempo, 120.0);
+        assert_eq!(
+            score.time_signatures,
+            vec![crate::neutrino_score::TimeSignature {
+                start_time_ns: 750_000_000,
+                numerator: 3,
+                denominator: 4,
+            }]
+        );
+        assert_eq!(
+            score.notes[1].length,
+            crate::neutrino_score::NoteLength::from_4th_note(1)
+        );
+        assert_eq!(
+            score.notes[2].length,
+            crate::neutrino_score::NoteLength::from_4th_note(2)
+        );
+    }
+
+    #[test]
+    fn score_keeps_tempo_changes_after_first_note() {
+        let notes = [note(0.0, 1.0, "あ"), note(1.0, 1.5, "あ")];
+        let tempos = [
+            TempoPayload {
+                time: 0.0,
+                tempo: 120.0,
+            },
+            TempoPayload {
+                time: 1.0,
+                tempo: 60.0,
+            },
+        ];
+        let score = task_notes_to_score(
+            &notes,
+            &tempos,
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(score.tempos[1].start_time_ns, 2_000_000_000);
+        assert_eq!(
+            score.notes[1].length,
+            crate::neutrino_score::NoteLength::from_4th_note(2)
+        );
+        assert_eq!(
+            score.notes[2].length,
+            crate::neutrino_score::NoteLength::from_8th_note(1)
+        );
+        assert_eq!(score.notes[3].start_time_ns, 2_500_000_000);
+        assert_eq!(
+            score.notes[3].length,
+            crate::neutrino_score::NoteLength::from_4th_note(1)
+        );
+    }
+
+    #[test]
+    fn score_aligns_measures_with_tunelab_time() {
+        let notes = [note(2.5, 3.0, "あ")];
+        let tempos = [TempoPayload {
+            time: 0.0,
+            tempo: 120.0,
+        }];
+        let time_signatures = [TimeSignaturePayload {
+            time: 0.0,
+            numerator: 4,
+            denominator: 4,
+        }];
+        let score = task_notes_to_score(
+            &notes,
+            &tempos,
+            &time_signatures,
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        // 最初のノートは2.5秒 = 1小節目の2拍目なので、小節の頭はその0.5秒前
+        assert_eq!(score.time_signatures[0].start_time_ns, 500_000_000);
+    }
+
+    #[test]
+    fn score_defaults_to_120_bpm() {
+        let score = task_notes_to_score(
+            &[note(0.0, 0.5, "あ")],
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(score.tempo_at(0), 120.0);
+        assert_eq!(
+            score.notes[1].length,
+            crate::neutrino_score::NoteLength::from_4th_note(1)
+        );
+    }
+
+    #[test]
+    fn off_grid_notes_do_not_drift() {
+        let notes = (0..40)
+            .map(|i| note(i as f64 * 0.515, (i + 1) as f64 * 0.515, "ら"))
+            .collect::<Vec<_>>();
+        let tempos = [TempoPayload {
+            time: 0.0,
+            tempo: 120.0,
+        }];
+        let score = task_notes_to_score(
+            &notes,
+            &tempos,
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        let ranges = crate::neutrino_score::compute_score_time_ranges_ns(&score);
+        let first_pau_end_ns = ranges[0].1;
+        for (i, &(start_ns, end_ns)) in ranges[1..=40].iter().enumerate() {
+            let expected_start_ns = (i as f64 * 515_000_000.0).round() as u64 + first_pau_end_ns;
+            let expected_end_ns =
+                ((i + 1) as f64 * 515_000_000.0).round() as u64 + first_pau_end_ns;
+            assert_eq!((start_ns, end_ns), (expected_start_ns, expected_end_ns));
+        }
+        assert_eq!(ranges[41].0, ranges[40].1);
+    }
+
+    #[test]
+    fn labels_follow_tempo_changes_from_payload() {
+        let mut payload = serde_json::from_str::<SynthesisTaskPayload>(
+            r#"{
+                "voiceId": "", "startTime": 0.0, "endTime": 2.5, "duration": 2.5,
+                "partProperties": { "tempo": 200 },
+                "tempos": [{ "time": 0.0, "tempo": 120 }, { "time": 1.0, "tempo": 60 }],
+                "notes": [
+                    { "startTime": 0.0, "endTime": 2.0, "pitch": 60, "lyric": "ら",
+                      "properties": {}, "phonemes": [] },
+                    { "startTime": 2.0, "endTime": 2.5, "pitch": 60, "lyric": "ら",
+                      "properties": {}, "phonemes": [] }
+                ],
+                "pitch": { "times": [], "values": [] }
+            }"#,
+        )
+        .expect("must parse");
+        payload.apply_part_properties(
+            &crate::properties::PropertySchema::default().resolve_part(&payload.part_properties),
+        );
+        let score = task_notes_to_score(
+            &payload.notes,
+            &payload.tempos,
+            &payload.time_signatures,
+            &payload.key_signatures,
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        let labels =
+            crate::neutrino_score::compose_labels_from_score(&score).expect("must compose");
+        let note_fields = |label: &crate::neutrino_score::TimedLabel| {
+            let note = &label.label.curr_note;
+            (
+                note.tempo.as_number(),
+                note.length_centisecond.as_integer(),
+                note.length_triplet_32nd.as_integer(),
+            )
+        };
+        assert_eq!(labels[1].label.phoneme.phoneme_id_current, "r");
+        assert_eq!(note_fields(&labels[1]), (Some(120.0), Some(200), Some(72)));
+        assert_eq!(labels[3].label.phoneme.phoneme_id_current, "r");
+        assert_eq!(note_fields(&labels[3]), (Some(60.0), Some(50), Some(12)));
+        assert_eq!(
+            labels[3].start_time_ns - labels[1].start_time_ns,
+            2_000_000_000
+        );
+    }
+
+    #[test]
+    fn part_properties_fill_missing_score_events() {
+        let mut payload = serde_json::from_str::<SynthesisTaskPayload>(
+            r#"{
+                "voiceId": "", "startTime": 0.0, "endTime": 1.0, "duration": 1.0,
+                "partProperties": { "tempo": 90, "timeSignatureNumerator": 3, "keySignature": "Eb" },
+                "timeSignatures": [{ "time": 0.0, "numerator": 6, "denominator": 8 }],
+                "notes": [], "pitch": { "times": [], "values": [] }
+            }"#,
+        )
+        .expect("must parse");
+        let schema = crate::properties::PropertySchema::default();
+        payload.apply_part_properties(&schema.resolve_part(&payload.part_properties));
+        assert_eq!(payload.tempos[0].tempo, 90.0);
+        assert_eq!(payload.time_signatures.len(), 1);
+        assert_eq!(payload.time_signatures[0].numerator, 6);
+        assert_eq!(payload.key_signatures[0].fifths, -3);
+
+        payload.key_signatures.clear();
+        payload.part_properties.clear();
+        payload.apply_part_properties(&schema.resolve_part(&payload.part_properties));
+        assert!(payload.key_signatures.is_empty());
+    }
+
+    #[test]
+    fn continuation_lyrics_carry_previous_vowel() {
+        let notes = [
+            note(0.0, 0.5, "か"),
+            note(0.5, 1.0, "ー"),
+            note(1.0, 1.5, "-"),
+            note(1.5, 2.0, "ん"),
+            note(2.0, 2.5, "+"),
+        ];
+        let score = task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        let sung = &score.notes[1..score.notes.len() - 1];
+        assert_eq!(
+            sung.iter()
+                .map(|n| n.phonemes.join(" "))
+                .collect::<Vec<_>>(),
+            ["k a", "a", "a", "N", "N"]
+        );
+        assert_eq!(
+            sung.iter().map(|n| n.slur).collect::<Vec<_>>(),
+            [false, true, true, false, true]
+        );
+    }
+
+    #[test]
+    fn multi_mora_lyrics_are_split_across_plus_notes() {
+        let notes = [
+            note(0.0, 0.5, "さくら"),
+            note(0.5, 1.0, "+"),
+            note(1.0, 1.5, "+"),
+            note(1.5, 2.0, "+"),
+            note(2.0, 2.5, "きょうは"),
+            note(2.5, 3.0, "+"),
+            note(3.0, 3.5, "sora"),
+        ];
+        let score = task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        let sung = &score.notes[1..score.notes.len() - 1];
+        assert_eq!(
+            sung.iter()
+                .map(|n| n.phonemes.join(" "))
+                .collect::<Vec<_>>(),
+            ["s a", "k u", "r a", "a", "ky o", "u h a", "s o r a"]
+        );
+        assert_eq!(
+            sung.iter().map(|n| n.slur).collect::<Vec<_>>(),
+            [false, false, false, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn continuation_lyric_needs_preceding_vowel() {
+        assert!(task_notes_to_score(
+            &[note(0.0, 0.5, "ー")],
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .is_err());
+        assert!(task_notes_to_score(
+            &[note(0.0, 0.5, "っ"), note(0.5, 1.0, "-")],
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn note_properties_become_marks() {
+        let mut marked = note(0.0, 0.5, "あ");
+        marked.properties =
+            serde_json::from_str(r#"{"dynamic": "pp", "accent": true, "staccato": 1.0}"#)
+                .expect("must parse");
+        let mut unknown = note(0.5, 1.0, "い");
+        unknown.properties =
+            serde_json::from_str(r#"{"dynamic": "sfz", "accent": false}"#).expect("must parse");
+        let score = task_notes_to_score(
+            &[marked, unknown],
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(
+            score.notes[1].marks,
+            crate::neutrino_score::NoteMarks {
+                dynamic: Some(crate::neutrino_score::Dynamic::Pianissimo),
+                accent: true,
+                staccato: true,
+            }
+        );
+        assert_eq!(score.notes[2].marks, Default::default());
+    }
+
+    #[test]
+    fn hairpins_from_note_properties() {
+        use crate::neutrino_score::HairpinKind;
+
+        let with_hairpin = |start_time: f64, end_time: f64, kind: &str| {
+            let mut note = note(start_time, end_time, "ら");
+            note.properties
+                .insert("hairpin".to_string(), serde_json::json!(kind));
+            note
+        };
+        let notes = [
+            with_hairpin(1.0, 1.5, "crescendo"),
+            with_hairpin(1.5, 2.0, "crescendo"),
+            with_hairpin(2.0, 2.5, "decrescendo"),
+            note(2.5, 3.0, "ら"),
+        ];
+        let score = task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        let spans = score
+            .hairpins
+            .iter()
+            .map(|h| (h.kind, h.start_time_ns, h.end_time_ns))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            [
+                (HairpinKind::Crescendo, 1_000_000_000, 2_000_000_000),
+                (HairpinKind::Decrescendo, 2_000_000_000, 2_500_000_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn key_signature_from_payload_or_estimation() {
+        let mut notes = ["ど", "れ", "み", "ふぁ", "そ", "ら", "し", "ど"]
+            .iter()
+            .enumerate()
+            .map(|(i, lyric)| note(i as f64 * 0.5, (i + 1) as f64 * 0.5, lyric))
+            .collect::<Vec<_>>();
+        for (note, pitch) in notes.iter_mut().zip([67, 69, 71, 72, 74, 76, 78, 79]) {
+            note.pitch = pitch;
+        }
+        let estimated = task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(estimated.key_signatures[0].fifths, 1);
+
+        let key_signatures = [
+            KeySignaturePayload {
+                time: 0.0,
+                fifths: -3,
+            },
+            KeySignaturePayload {
+                time: 2.0,
+                fifths: 2,
+            },
+        ];
+        let score = task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &key_signatures,
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(
+            score.key_signatures,
+            [
+                crate::neutrino_score::KeySignature {
+                    start_time_ns: 0,
+                    fifths: -3,
+                },
+                crate::neutrino_score::KeySignature {
+                    start_time_ns: 3_000_000_000,
+                    fifths: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn gaps_become_rests_and_overlaps_are_truncated() {
+        let notes = [
+            note(0.0, 0.5, "あ"),
+            note(0.52, 1.0, "い"),
+            note(0.9, 1.5, "う"),
+            note(2.0, 2.5, "え"),
+        ];
+        let score = task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(
+            score
+                .notes
+                .iter()
+                .map(|n| (n.phonemes.join(" "), i32::from(n.length)))
+                .collect::<Vec<_>>(),
+            [
+                ("pau".to_string(), 48),
+                ("a".to_string(), 25),
+                ("i".to_string(), 18),
+                ("u".to_string(), 29),
+                ("pau".to_string(), 24),
+                ("e".to_string(), 24),
+                ("pau".to_string(), 48),
+            ]
+        );
+        assert_eq!(score.notes[4].start_time_ns, 2_500_000_000);
+        assert_eq!(
+            payload_note_indices(&score),
+            [None, Some(0), Some(1), Some(2), None, Some(3), None]
+        );
+    }
+
+    #[test]
+    fn breaths_are_inserted_before_phrases() {
+        let notes = [
+            note(0.0, 0.5, "あ"),
+            note(0.6, 1.0, "い"),
+            note(1.25, 1.5, "う"),
+        ];
+        let options = ScoreOptions {
+            breath: Some(BreathOptions {
+                min_gap_ns: 250_000_000,
+                length_ns: 200_000_000,
+            }),
+            ..Default::default()
+        };
+        let score = task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &[],
+            &options,
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(
+            score
+                .notes
+                .iter()
+                .map(|n| (n.phonemes.join(" "), i32::from(n.length)))
+                .collect::<Vec<_>>(),
+            [
+                ("pau".to_string(), 38),
+                ("br".to_string(), 10),
+                ("a".to_string(), 24),
+                ("pau".to_string(), 5),
+                ("i".to_string(), 19),
+                ("pau".to_string(), 2),
+                ("br".to_string(), 10),
+                ("u".to_string(), 12),
+                ("pau".to_string(), 48),
+            ]
+        );
+        assert_eq!(score.notes[2].start_time_ns, 1_000_000_000);
+        assert_eq!(
+            payload_note_indices(&score),
+            [
+                None,
+                None,
+                Some(0),
+                None,
+                Some(1),
+                None,
+                None,
+                Some(2),
+                None
+            ]
+        );
+    }
+
+    #[derive(Debug)]
+    struct WhitespacePhonemizer;
+
+    impl crate::phonemizer::Phonemizer for WhitespacePhonemizer {
+        fn language(&self) -> &str {
+            "ENG"
+        }
+
+        fn phonemize(&self, lyric: &str) -> anyhow::Result<Vec<String>> {
+            Ok(lyric.split_whitespace().map(str::to_string).collect())
+        }
+
+        fn is_vowel(&self, phoneme: &str) -> bool {
+            ["ah", "eh", "ow"].contains(&phoneme)
+        }
+    }
+
+    #[test]
+    fn phonemizer_sets_phonemes_and_language() {
+        let notes = [
+            note(0.0, 0.5, "hh eh"),
+            note(0.5, 1.0, "l ow"),
+            note(1.0, 1.5, "-"),
+        ];
+        let score = task_notes_to_score(
+            &notes,
+            &[],
+            &[],
+            &[],
+            &ScoreOptions::default(),
+            &WhitespacePhonemizer,
+        )
+        .expect("must convert");
+        assert_eq!(score.notes[1].phonemes, ["hh", "eh"]);
+        // NOTE: 伸ばし記号は音素化の母音判定で直前の母音を引き継ぐ
+        assert_eq!(score.notes[3].phonemes, ["ow"]);
+        assert!(score.notes[3].slur);
+        assert!(score
+            .notes
+            .iter()
+            .all(|n| n.language.as_deref() == Some("ENG")));
+    }
+
     #[test]
     fn loose_f64_treats_negative_max_as_nan() {
         let v: LooseF64 = serde_json::from_str("-1.7976931348623157e308").expect("must parse");
         assert!(!v.is_finite());
     }
+
+    #[test]
+    fn previews_continuation_lyrics_like_synthesis() {
+        let preview = |lyrics: &[&str]| {
+            preview_lyrics(
+                &lyrics.iter().map(|l| l.to_string()).collect::<Vec<_>>(),
+                &crate::phonemizer::JapanesePhonemizer,
+            )
+            .into_iter()
+            .map(|p| (p.phonemes, p.error))
+            .collect::<Vec<_>>()
+        };
+        let ok = |phonemes: &[&str]| (phonemes.iter().map(|p| p.to_string()).collect(), None);
+        assert_eq!(
+            preview(&["か", "-", "ー"]),
+            [ok(&["k", "a"]), ok(&["a"]), ok(&["a"])]
+        );
+        assert_eq!(
+            preview(&["さくら", "+", "+", "-"]),
+            [
+                ok(&["s", "a"]),
+                ok(&["k", "u"]),
+                ok(&["r", "a"]),
+                ok(&["a"])
+            ]
+        );
+        assert_eq!(
+            preview(&["さくら", "+"]),
+            [ok(&["s", "a"]), ok(&["k", "u", "r", "a"])]
+        );
+        assert_eq!(preview(&["-"]), [ok(&[])]);
+        assert_eq!(preview(&["ー"]), [ok(&[])]);
+        assert_eq!(preview(&["+"]), [ok(&[])]);
+        assert_eq!(preview(&["漢字", "-"])[1], ok(&[]));
+        assert!(preview(&["漢字", "-"])[0].1.is_some());
+    }
 }
@@ -0,0 +1,352 @@
+use crate::synthesizer::TimingLabel;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizeOptions {
+    pub min_consonant_duration_ns: u64,
+    pub min_vowel_duration_ns: u64,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            min_consonant_duration_ns: 10_000_000,
+            min_vowel_duration_ns: 20_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AdjustmentKind {
+    ClampedToNote,
+    Reordered,
+    OverlapRepaired,
+    MinimumDuration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adjustment {
+    pub phoneme_index: usize,
+    pub note_index: usize,
+    pub phoneme: String,
+    pub kinds: Vec<AdjustmentKind>,
+    pub original_start_time_ns: u64,
+    pub original_end_time_ns: u64,
+    pub start_time_ns: u64,
+    pub end_time_ns: u64,
+}
+
+fn min_duration_ns(
+    phoneme: &str,
+    options: &NormalizeOptions,
+    phonemizer: &dyn crate::phonemizer::Phonemizer,
+) -> u64 {
+    if phonemizer.is_vowel(phoneme) {
+        options.min_vowel_duration_ns
+    } else {
+        options.min_consonant_duration_ns
+    }
+}
+
+// NOTE: group_sizes[i]はノートiに属する音素の数、note_ranges_ns[i]はそのノートのスコア上の範囲
+pub fn normalize_timings(
+    timings: &[TimingLabel],
+    group_sizes: &[usize],
+    note_ranges_ns: &[(u64, u64)],
+    options: &NormalizeOptions,
+    phonemizer: &dyn crate::phonemizer::Phonemizer,
+) -> (Vec<TimingLabel>, Vec<Adjustment>) {
+    let note_indices = group_sizes
+        .iter()
+        .enumerate()
+        .flat_map(|(note_index, &size)| std::iter::repeat_n(note_index, size))
+        .collect::<Vec<_>>();
+    let mut kinds = vec![Vec::<AdjustmentKind>::new(); timings.len()];
+    let mut result = timings.to_vec();
+
+    // 1. 前後のノートの範囲内に収める（子音はノートの開始より前に来るので前のノートまで許す）
+    let bounds = note_indices
+        .iter()
+        .map(|&note_index| neighbourhood(note_ranges_ns, note_index))
+        .collect::<Vec<_>>();
+    for (i, label) in result.iter_mut().enumerate() {
+        let Some((lower, upper)) = bounds[i] else {
+            continue;
+        };
+        let start = label.start_time_ns.clamp(lower, upper);
+        let end = label.end_time_ns.clamp(lower, upper);
+        if start != label.start_time_ns || end != label.end_time_ns {
+            push_kind(&mut kinds[i], AdjustmentKind::ClampedToNote);
+            label.start_time_ns = start;
+            label.end_time_ns = end;
+        }
+    }
+
+    // 2. 境界を単調にして最小長を確保する
+    let mut cursor = 0;
+    let mut previous_start = 0;
+    for (i, label) in result.iter_mut().enumerate() {
+        if label.end_time_ns < label.start_time_ns {
+            std::mem::swap(&mut label.start_time_ns, &mut label.end_time_ns);
+            push_kind(&mut kinds[i], AdjustmentKind::Reordered);
+        }
+        if label.start_time_ns < cursor {
+            push_kind(
+                &mut kinds[i],
+                if label.start_time_ns < previous_start {
+                    AdjustmentKind::Reordered
+                } else {
+                    AdjustmentKind::OverlapRepaired
+                },
+            );
+            label.start_time_ns = cursor;
+            label.end_time_ns = label.end_time_ns.max(cursor);
+        }
+        let min_end = label.start_time_ns + min_duration_ns(&label.phoneme, options, phonemizer);
+        if label.end_time_ns < min_end {
+            label.end_time_ns = min_end;
+            push_kind(&mut kinds[i], AdjustmentKind::MinimumDuration);
+        }
+        previous_start = label.start_time_ns;
+        cursor = label.end_time_ns;
+    }
+
+    // 3. 最小長で後ろにはみ出した分を後ろから詰め直す
+    let mut limit = u64::MAX;
+    for i in (0..result.len()).rev() {
+        let upper = bounds[i].map_or(u64::MAX, |(_, upper)| upper).min(limit);
+        let label = &mut result[i];
+        if label.end_time_ns > upper {
+            let duration = label.end_time_ns - label.start_time_ns;
+            label.end_time_ns = upper;
+            label.start_time_ns = upper.saturating_sub(duration);
+            push_kind(&mut kinds[i], AdjustmentKind::MinimumDuration);
+        }
+        limit = label.start_time_ns;
+    }
+
+    // 4. 先頭まで詰めきれなかった場合に備えて単調性を確認し直す
+    let mut cursor = 0;
+    for (i, label) in result.iter_mut().enumerate() {
+        if label.start_time_ns < cursor {
+            label.start_time_ns = cursor;
+            label.end_time_ns = label.end_time_ns.max(cursor);
+            push_kind(&mut kinds[i], AdjustmentKind::OverlapRepaired);
+        }
+        cursor = label.end_time_ns;
+    }
+
+    let adjustments = result
+        .iter()
+        .zip(timings)
+        .zip(kinds)
+        .enumerate()
+        .filter(|(_, ((after, before), _))| {
+            after.start_time_ns != before.start_time_ns || after.end_time_ns != before.end_time_ns
+        })
+        .map(|(i, ((after, before), kinds))| Adjustment {
+            phoneme_index: i,
+            note_index: note_indices.get(i).copied().unwrap_or(0),
+            phoneme: after.phoneme.clone(),
+            kinds,
+            original_start_time_ns: before.start_time_ns,
+            original_end_time_ns: before.end_time_ns,
+            start_time_ns: after.start_time_ns,
+            end_time_ns: after.end_time_ns,
+        })
+        .collect();
+
+    (result, adjustments)
+}
+
+// NOTE: 子音（最初の母音より前の音素）の時間をノートの開始位置を基準に伸縮する
+pub fn scale_consonants(
+    groups: &mut [Vec<TimingLabel>],
+    note_ranges_ns: &[(u64, u64)],
+    scales: &[f64],
+    phonemizer: &dyn crate::phonemizer::Phonemizer,
+) {
+    for note_index in 0..groups.len() {
+        let scale = scales.get(note_index).copied().unwrap_or(1.0);
+        if !scale.is_finite() || scale < 0.0 || (scale - 1.0).abs() < f64::EPSILON {
+            continue;
+        }
+        let Some(&(onset, _)) = note_ranges_ns.get(note_index) else {
+            continue;
+        };
+        let group = &mut groups[note_index];
+        let Some(first_vowel) = group
+            .iter()
+            .position(|label| phonemizer.is_vowel(&label.phoneme))
+        else {
+            continue;
+        };
+        if first_vowel == 0 {
+            continue;
+        }
+        let scale_time = |time_ns: u64| {
+            let scaled = onset as f64 + (time_ns as f64 - onset as f64) * scale;
+            scaled.max(0.0).round() as u64
+        };
+        for label in &mut group[..first_vowel] {
+            label.start_time_ns = scale_time(label.start_time_ns);
+            label.end_time_ns = scale_time(label.end_time_ns);
+        }
+        group[first_vowel].start_time_ns = group[first_vowel - 1].end_time_ns;
+        let consonant_start = group[0].start_time_ns;
+        if let Some(previous) = note_index.checked_sub(1).and_then(|i| groups[i].last_mut()) {
+            previous.end_time_ns = consonant_start;
+        }
+    }
+}
+
+fn neighbourhood(note_ranges_ns: &[(u64, u64)], note_index: usize) -> Option<(u64, u64)> {
+    let current = note_ranges_ns.get(note_index)?;
+    let lower = note_index
+        .checked_sub(1)
+        .and_then(|i| note_ranges_ns.get(i))
+        .map_or(0, |(start, _)| *start);
+    let upper = note_ranges_ns
+        .get(note_index + 1)
+        .map_or(current.1, |(_, end)| *end);
+    Some((lower, upper))
+}
+
+fn push_kind(kinds: &mut Vec<AdjustmentKind>, kind: AdjustmentKind) {
+    if !kinds.contains(&kind) {
+        kinds.push(kind);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(start_ms: u64, end_ms: u64, phoneme: &str) -> TimingLabel {
+        TimingLabel {
+            start_time_ns: start_ms * 1_000_000,
+            end_time_ns: end_ms * 1_000_000,
+            phoneme: phoneme.to_string(),
+        }
+    }
+
+    fn ranges() -> Vec<(u64, u64)> {
+        vec![
+            (0, 1_000_000_000),
+            (1_000_000_000, 1_500_000_000),
+            (1_500_000_000, 2_500_000_000),
+        ]
+    }
+
+    #[test]
+    fn scales_consonants_around_onset() {
+        let mut groups = vec![
+            vec![label(0, 900, "pau")],
+            vec![label(900, 1000, "k"), label(1000, 1500, "a")],
+            vec![label(1400, 1520, "s"), label(1520, 2000, "a")],
+        ];
+        scale_consonants(
+            &mut groups,
+            &ranges(),
+            &[1.0, 2.0, 0.5],
+            &crate::phonemizer::JapanesePhonemizer,
+        );
+        assert_eq!(groups[0][0].end_time_ns, 800_000_000);
+        assert_eq!(groups[1][0].start_time_ns, 800_000_000);
+        assert_eq!(groups[1][0].end_time_ns, 1_000_000_000);
+        assert_eq!(groups[1][1].end_time_ns, 1_450_000_000);
+        assert_eq!(groups[2][0].start_time_ns, 1_450_000_000);
+        assert_eq!(groups[2][0].end_time_ns, 1_510_000_000);
+        assert_eq!(groups[2][1].start_time_ns, 1_510_000_000);
+    }
+
+    #[test]
+    fn keeps_valid_timings() {
+        let timings = vec![
+            label(0, 950, "pau"),
+            label(950, 1500, "k"),
+            label(1500, 1550, "a"),
+        ];
+        let (result, adjustments) = normalize_timings(
+            &timings,
+            &[1, 1, 1],
+            &ranges(),
+            &NormalizeOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        );
+        assert!(adjustments.is_empty());
+        assert_eq!(result[1].start_time_ns, timings[1].start_time_ns);
+    }
+
+    #[test]
+    fn repairs_overlap_and_zero_length() {
+        let timings = vec![
+            label(0, 1000, "pau"),
+            label(990, 1000, "k"),
+            label(1000, 1000, "a"),
+            label(1500, 2500, "pau"),
+        ];
+        let (result, adjustments) = normalize_timings(
+            &timings,
+            &[1, 2, 1],
+            &ranges(),
+            &NormalizeOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        );
+        for (prev, next) in result.iter().zip(result.iter().skip(1)) {
+            assert!(prev.end_time_ns <= next.start_time_ns);
+        }
+        assert_eq!(result[1].start_time_ns, 1_000_000_000);
+        assert_eq!(result[1].end_time_ns, 1_010_000_000);
+        assert_eq!(result[2].end_time_ns, 1_030_000_000);
+        assert_eq!(adjustments.len(), 2);
+        assert_eq!(
+            adjustments[0].kinds,
+            vec![
+                AdjustmentKind::OverlapRepaired,
+                AdjustmentKind::MinimumDuration
+            ]
+        );
+        assert_eq!(adjustments[0].note_index, 1);
+    }
+
+    #[test]
+    fn fixes_out_of_order_and_clamps() {
+        let timings = vec![
+            label(0, 1000, "pau"),
+            label(1200, 1100, "k"),
+            label(1100, 3000, "a"),
+        ];
+        let (result, adjustments) = normalize_timings(
+            &timings,
+            &[1, 2],
+            &ranges()[..2],
+            &NormalizeOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        );
+        assert_eq!(result[1].start_time_ns, 1_100_000_000);
+        assert_eq!(result[1].end_time_ns, 1_200_000_000);
+        assert_eq!(result[2].start_time_ns, 1_200_000_000);
+        assert_eq!(result[2].end_time_ns, 1_500_000_000);
+        assert!(adjustments[0].kinds.contains(&AdjustmentKind::Reordered));
+        assert!(adjustments[1]
+            .kinds
+            .contains(&AdjustmentKind::ClampedToNote));
+    }
+
+    #[test]
+    fn minimum_duration_does_not_run_past_the_last_note() {
+        let timings = vec![label(1499, 1500, "k"), label(1500, 1500, "a")];
+        let (result, _) = normalize_timings(
+            &timings,
+            &[2],
+            &[(1_000_000_000, 1_500_000_000)],
+            &NormalizeOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        );
+        assert_eq!(result[1].end_time_ns, 1_500_000_000);
+        assert_eq!(result[1].start_time_ns, 1_480_000_000);
+        assert_eq!(result[0].end_time_ns, 1_480_000_000);
+        assert_eq!(result[0].start_time_ns, 1_470_000_000);
+    }
+}
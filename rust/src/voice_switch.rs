@@ -0,0 +1,392 @@
+// NOTE: 区間の前後に隣の区間のノートを文脈として入れておくと、つなぎ目の子音や「+」が自然になる
+const CONTEXT_NOTES: usize = 1;
+const CROSSFADE_SECONDS: f64 = 0.05;
+
+#[derive(Debug, Clone)]
+pub struct VoiceSegment {
+    pub payload: crate::synthesizer::SynthesisTaskPayload,
+    // NOTE: 区間内のノート番号から元のノート番号への対応。文脈として入れたノートはNone
+    pub note_indices: Vec<Option<usize>>,
+}
+
+impl VoiceSegment {
+    fn first_note_start_time(&self) -> f64 {
+        self.note_indices
+            .iter()
+            .position(Option::is_some)
+            .map_or(f64::NEG_INFINITY, |i| self.payload.notes[i].start_time)
+    }
+}
+
+fn is_continuation(note: &crate::synthesizer::SynthesisNotePayload) -> bool {
+    note.phonemes.is_empty() && crate::synthesizer::CONTINUATION_LYRICS.contains(&note.lyric.trim())
+}
+
+fn is_split_target(note: &crate::synthesizer::SynthesisNotePayload) -> bool {
+    note.phonemes.is_empty() && note.lyric.trim() == "+"
+}
+
+// NOTE: 同じ音源が続くノートをひとつの区間にまとめる
+pub fn split_by_voice(
+    payload: &crate::synthesizer::SynthesisTaskPayload,
+    note_voices: &[String],
+) -> Vec<VoiceSegment> {
+    let mut ranges: Vec<(String, std::ops::Range<usize>)> = Vec::new();
+    for (i, voice) in note_voices.iter().enumerate() {
+        match ranges.last_mut() {
+            Some((last_voice, range)) if last_voice == voice => range.end = i + 1,
+            _ => ranges.push((voice.clone(), i..i + 1)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(voice_id, range)| {
+            // NOTE: 伸ばし記号は直前の母音を、「+」は前のノートのモーラを引き継ぐので、全体で歌詞を割り当てたときと同じになるよう
+            //       文脈は伸ばし記号でないノートまでさかのぼり、後ろに続く「+」もすべて入れる
+            let mut context_start = range.start.saturating_sub(CONTEXT_NOTES);
+            while context_start > 0 && is_continuation(&payload.notes[context_start]) {
+                context_start -= 1;
+            }
+            let mut context_end = (range.end + CONTEXT_NOTES).min(payload.notes.len());
+            while context_end < payload.notes.len() && is_split_target(&payload.notes[context_end])
+            {
+                context_end += 1;
+            }
+            let note_indices = (context_start..context_end)
+                .map(|i| range.contains(&i).then_some(i))
+                .collect::<Vec<_>>();
+            let local_index = |index: Option<usize>| {
+                index
+                    .filter(|i| (context_start..context_end).contains(i))
+                    .map(|i| i - context_start)
+            };
+            let notes = payload.notes[context_start..context_end]
+                .iter()
+                .map(|note| crate::synthesizer::SynthesisNotePayload {
+                    last_index: local_index(note.last_index),
+                    next_index: local_index(note.next_index),
+                    ..note.clone()
+                })
+                .collect();
+            VoiceSegment {
+                payload: crate::synthesizer::SynthesisTaskPayload {
+                    voice_id,
+                    notes,
+                    ..payload.clone()
+                },
+                note_indices,
+            }
+        })
+        .collect()
+}
+
+// NOTE: 区間の切り替えは後ろの区間の最初の音素の開始位置。その直前で前の区間からクロスフェードする
+fn crossfade_gain(time: f64, boundary: f64) -> f64 {
+    ((time - (boundary - CROSSFADE_SECONDS)) / CROSSFADE_SECONDS).clamp(0.0, 1.0)
+}
+
+pub fn merge_responses(
+    segments: &[VoiceSegment],
+    responses: Vec<crate::synthesizer::SynthesisResponse>,
+    note_count: usize,
+) -> anyhow::Result<crate::synthesizer::SynthesisResponse> {
+    let Some(sample_rate) = responses.first().map(|r| r.sample_rate) else {
+        anyhow::bail!("No voice segments to merge");
+    };
+    if let Some(response) = responses.iter().find(|r| r.sample_rate != sample_rate) {
+        anyhow::bail!(
+            "Voices have different sample rates: {} and {}",
+            sample_rate,
+            response.sample_rate
+        );
+    }
+
+    let mut boundaries = vec![f64::NEG_INFINITY];
+    for (segment, response) in segments.iter().zip(&responses).skip(1) {
+        let first_phoneme_time = response
+            .note_phonemes
+            .iter()
+            .filter(|n| segment.note_indices[n.note_index].is_some())
+            .min_by_key(|n| n.note_index)
+            .and_then(|n| n.phonemes.first())
+            .map_or(segment.first_note_start_time(), |p| p.start_time);
+        let previous = *boundaries.last().unwrap();
+        boundaries.push(first_phoneme_time.max(previous));
+    }
+    boundaries.push(f64::INFINITY);
+
+    let sample_rate_f64 = sample_rate as f64;
+    let start_time = responses
+        .iter()
+        .map(|r| r.start_time)
+        .fold(f64::INFINITY, f64::min);
+    let end_time = responses
+        .iter()
+        .map(|r| r.start_time + r.samples.len() as f64 / sample_rate_f64)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let sample_count = ((end_time - start_time) * sample_rate_f64).round().max(0.0) as usize;
+    let samples = (0..sample_count)
+        .map(|i| {
+            let time = start_time + i as f64 / sample_rate_f64;
+            responses
+                .iter()
+                .enumerate()
+                .map(|(j, response)| {
+                    let gain = crossfade_gain(time, boundaries[j])
+                        * (1.0 - crossfade_gain(time, boundaries[j + 1]));
+                    let index = ((time - response.start_time) * sample_rate_f64).round();
+                    let sample = if index >= 0.0 {
+                        response.samples.get(index as usize).copied().unwrap_or(0.0)
+                    } else {
+                        0.0
+                    };
+                    sample * gain as f32
+                })
+                .sum::<f32>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut pitch_times = Vec::new();
+    let mut pitch_values = Vec::new();
+    let mut note_phonemes = Vec::new();
+    let mut timing_adjustments = Vec::new();
+    for (j, (segment, response)) in segments.iter().zip(responses).enumerate() {
+        let owns = |time: f64| boundaries[j] <= time && time < boundaries[j + 1];
+        for (time, value) in response.pitch_times.into_iter().zip(response.pitch_values) {
+            if owns(time) {
+                pitch_times.push(time);
+                pitch_values.push(value);
+            }
+        }
+        note_phonemes.extend(response.note_phonemes.into_iter().filter_map(|n| {
+            segment.note_indices[n.note_index].map(|note_index| crate::synthesizer::NotePhonemes {
+                note_index,
+                phonemes: n.phonemes,
+            })
+        }));
+        // NOTE: 休符の調整はその時刻を受け持つ区間のものだけ残す
+        timing_adjustments.extend(response.timing_adjustments.into_iter().filter_map(
+            |adjustment| match adjustment.note_index {
+                Some(index) => segment.note_indices[index].map(|note_index| {
+                    crate::synthesizer::TimingAdjustment {
+                        note_index: Some(note_index),
+                        ..adjustment
+                    }
+                }),
+                None => owns(adjustment.start_time).then_some(adjustment),
+            },
+        ));
+    }
+    note_phonemes.sort_by_key(|n| n.note_index);
+
+    Ok(crate::synthesizer::SynthesisResponse {
+        start_time,
+        sample_rate,
+        sample_count: samples.len() as _,
+        samples,
+        pitch_times,
+        pitch_values,
+        phoneme_count: note_phonemes.iter().map(|n| n.phonemes.len()).sum(),
+        note_phonemes,
+        timing_adjustments,
+        note_count,
+        property_count: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> crate::synthesizer::SynthesisTaskPayload {
+        serde_json::from_str(
+            r#"{
+                "voiceId": "A", "startTime": 0.0, "endTime": 2.0, "duration": 2.0,
+                "partProperties": {},
+                "notes": [
+                    { "startTime": 0.0, "endTime": 0.5, "pitch": 60, "lyric": "あ",
+                      "nextIndex": 1, "properties": {}, "phonemes": [] },
+                    { "startTime": 0.5, "endTime": 1.0, "pitch": 60, "lyric": "い",
+                      "lastIndex": 0, "nextIndex": 2, "properties": {}, "phonemes": [] },
+                    { "startTime": 1.0, "endTime": 1.5, "pitch": 60, "lyric": "う",
+                      "lastIndex": 1, "nextIndex": 3, "properties": {}, "phonemes": [] },
+                    { "startTime": 1.5, "endTime": 2.0, "pitch": 60, "lyric": "え",
+                      "lastIndex": 2, "properties": {}, "phonemes": [] }
+                ],
+                "pitch": { "times": [], "values": [] }
+            }"#,
+        )
+        .expect("must parse")
+    }
+
+    fn response(
+        start_time: f64,
+        value: f32,
+        note_phonemes: &[(usize, f64)],
+    ) -> crate::synthesizer::SynthesisResponse {
+        crate::synthesizer::SynthesisResponse {
+            start_time,
+            sample_rate: 100,
+            sample_count: 300,
+            samples: vec![value; 300],
+            pitch_times: (0..30).map(|i| start_time + i as f64 * 0.1).collect(),
+            pitch_values: vec![value as f64; 30],
+            note_phonemes: note_phonemes
+                .iter()
+                .map(|&(note_index, time)| crate::synthesizer::NotePhonemes {
+                    note_index,
+                    phonemes: vec![crate::synthesizer::SynthesizedPhoneme {
+                        symbol: "a".to_string(),
+                        start_time: time,
+                        end_time: time + 0.5,
+                    }],
+                })
+                .collect(),
+            timing_adjustments: Vec::new(),
+            note_count: note_phonemes.len(),
+            phoneme_count: note_phonemes.len(),
+            property_count: 0,
+        }
+    }
+
+    #[test]
+    fn splits_consecutive_voices_with_context_notes() {
+        let voices = ["A", "A", "B", "A"].map(str::to_string);
+        let segments = split_by_voice(&payload(), &voices);
+        assert_eq!(
+            segments
+                .iter()
+                .map(|s| (s.payload.voice_id.as_str(), s.note_indices.clone()))
+                .collect::<Vec<_>>(),
+            [
+                ("A", vec![Some(0), Some(1), None]),
+                ("B", vec![None, Some(2), None]),
+                ("A", vec![None, Some(3)]),
+            ]
+        );
+        let notes = &segments[1].payload.notes;
+        assert_eq!(notes[0].lyric, "い");
+        assert_eq!(notes[0].last_index, None);
+        assert_eq!(notes[1].last_index, Some(0));
+        assert_eq!(notes[2].next_index, None);
+    }
+
+    #[test]
+    fn crossfades_at_the_first_phoneme_of_the_next_voice() {
+        let voices = ["A", "A", "B", "B"].map(str::to_string);
+        let segments = split_by_voice(&payload(), &voices);
+        let merged = merge_responses(
+            &segments,
+            vec![
+                response(-0.5, 1.0, &[(0, 0.0), (1, 0.5), (2, 1.0)]),
+                response(0.0, 3.0, &[(0, 0.5), (1, 0.9), (2, 1.5)]),
+            ],
+            4,
+        )
+        .expect("must merge");
+
+        assert_eq!(merged.start_time, -0.5);
+        assert_eq!(merged.samples.len(), 350);
+        let sample_at = |time: f64| merged.samples[((time + 0.5) * 100.0).round() as usize];
+        assert_eq!(sample_at(0.8), 1.0);
+        assert!((sample_at(0.88) - 2.2).abs() < 1e-4);
+        assert_eq!(sample_at(0.9), 3.0);
+
+        assert_eq!(
+            merged
+                .note_phonemes
+                .iter()
+                .map(|n| (n.note_index, n.phonemes[0].start_time))
+                .collect::<Vec<_>>(),
+            [(0, 0.0), (1, 0.5), (2, 0.9), (3, 1.5)]
+        );
+        assert_eq!(merged.phoneme_count, 4);
+        assert!(merged
+            .pitch_times
+            .iter()
+            .zip(&merged.pitch_values)
+            .all(|(&t, &v)| v == if t < 0.9 { 1.0 } else { 3.0 }));
+    }
+
+    #[test]
+    fn rejects_mismatched_sample_rates() {
+        let voices = ["A", "B", "B", "B"].map(str::to_string);
+        let segments = split_by_voice(&payload(), &voices);
+        let mut second = response(0.0, 1.0, &[]);
+        second.sample_rate = 48000;
+        assert!(merge_responses(&segments, vec![response(0.0, 1.0, &[]), second], 4).is_err());
+    }
+
+    fn lyric_payload(lyrics: &[&str]) -> crate::synthesizer::SynthesisTaskPayload {
+        let notes = lyrics
+            .iter()
+            .enumerate()
+            .map(|(i, lyric)| {
+                serde_json::json!({
+                    "startTime": i as f64 * 0.5,
+                    "endTime": (i + 1) as f64 * 0.5,
+                    "pitch": 60,
+                    "lyric": lyric,
+                    "properties": {},
+                    "phonemes": []
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({
+            "voiceId": "A", "startTime": 0.0, "endTime": lyrics.len() as f64 * 0.5,
+            "duration": lyrics.len() as f64 * 0.5, "partProperties": {},
+            "notes": notes, "pitch": { "times": [], "values": [] }
+        }))
+        .expect("must parse")
+    }
+
+    fn phonemes_by_note(payload: &crate::synthesizer::SynthesisTaskPayload) -> Vec<Vec<String>> {
+        let score = crate::synthesizer::task_notes_to_score(
+            &payload.notes,
+            &[],
+            &[],
+            &[],
+            &crate::synthesizer::ScoreOptions::default(),
+            &crate::phonemizer::JapanesePhonemizer,
+        )
+        .expect("must convert");
+        let mut phonemes = vec![Vec::new(); payload.notes.len()];
+        for (note, index) in score
+            .notes
+            .iter()
+            .zip(crate::synthesizer::payload_note_indices(&score))
+        {
+            if let Some(index) = index {
+                phonemes[index] = note.phonemes.clone();
+            }
+        }
+        phonemes
+    }
+
+    #[test]
+    fn continuation_lyrics_at_voice_changes_match_the_whole_part() {
+        for (lyrics, voices) in [
+            (vec!["あ", "-", "い"], ["A", "A", "B"].as_slice()),
+            (vec!["あ", "ー", "-", "い"], &["A", "A", "B", "B"]),
+            (vec!["さくら", "+", "い"], &["A", "A", "B"]),
+            (vec!["さくら", "+", "+"], &["A", "B", "B"]),
+            (vec!["さくら", "+", "+", "い"], &["A", "A", "B", "A"]),
+        ] {
+            let payload = lyric_payload(&lyrics);
+            let whole = phonemes_by_note(&payload);
+            let voices = voices.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+            for segment in split_by_voice(&payload, &voices) {
+                // NOTE: 文脈として入れたノートもつなぎ目の音素に効くので、全体と同じ音素になっているか見る
+                let phonemes = phonemes_by_note(&segment.payload);
+                for (note, phonemes) in segment.payload.notes.iter().zip(phonemes) {
+                    let index = payload
+                        .notes
+                        .iter()
+                        .position(|n| n.start_time == note.start_time)
+                        .expect("must exist");
+                    assert_eq!(phonemes, whole[index], "{:?} note {}", lyrics, index);
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
 using System.Globalization;
+using System.Reflection;
 using System.Runtime.InteropServices;
 using System.Runtime.CompilerServices;
 using System.Text;
 using System.Text.Json;
 using TuneLab.Base.Properties;
 using TuneLab.Base.Structures;
+using TuneLab.Base.Utils;
 using TuneLab.Extensions.Voices;
 
 namespace NeutrinoTau;
@@ -129,10 +131,9 @@ public unsafe sealed class NeutrinoTauSynthesisTask : ISynthesisTask
       StartTime = _startTime,
       EndTime = _endTime,
       Duration = Math.Max(0.0, _endTime - _startTime),
-      StyleShift = ResolveNumericPartProperty(_data.PartProperties, "styleshift"),
-      WaveformStyleShift = ResolveNumericPartProperty(_data.PartProperties, "waveformstyleshift"),
-      PitchShiftCents = ResolveNumericPartProperty(_data.PartProperties, "pitchshiftcents", roundToInteger: false),
       PartProperties = ConvertPropertyObject(_data.PartProperties),
+      Tempos = CollectTempos(),
+      TimeSignatures = CollectTimeSignatures(),
       Notes = notePayloads,
       Pitch = new PitchPayload
       {
@@ -142,6 +143,99 @@ public unsafe sealed class NeutrinoTauSynthesisTask : ISynthesisTask
     };
   }
 
+  // NOTE: ISynthesisDataはテンポや拍子を公開していないので、TuneLab本体のパート（ITimeline）から読めれば読む。
+  //       読めなければ空のまま送り、ネイティブ側でパートプロパティから補う。TuneLabの内部に頼るので、読めなかった理由はログに残す
+  private List<TempoPayload> CollectTempos()
+  {
+    try
+    {
+      var tempoManager = GetMemberValue(_data, "TempoManager");
+      var tempos = GetEnumerableMember(tempoManager, "Tempos")
+        .Select(tempo => new TempoPayload
+        {
+          Time = TickToTime(tempoManager, GetNumberMember(tempo, "Pos")),
+          Tempo = GetNumberMember(tempo, "Bpm"),
+        })
+        .ToList();
+      return SelectEventsInRange(tempos, tempo => tempo.Time);
+    }
+    catch (Exception ex)
+    {
+      Log.Info($"Failed to read the project's tempos, using the part properties instead: {DescribeReflectionError(ex)}");
+      return [];
+    }
+  }
+
+  private List<TimeSignaturePayload> CollectTimeSignatures()
+  {
+    try
+    {
+      var tempoManager = GetMemberValue(_data, "TempoManager");
+      var timeSignatureManager = GetMemberValue(_data, "TimeSignatureManager");
+      var timeSignatures = GetEnumerableMember(timeSignatureManager, "TimeSignatures")
+        .Select(timeSignature => new TimeSignaturePayload
+        {
+          Time = TickToTime(tempoManager, GetNumberMember(timeSignature, "Pos")),
+          Numerator = (int)GetNumberMember(timeSignature, "Numerator"),
+          Denominator = (int)GetNumberMember(timeSignature, "Denominator"),
+        })
+        .ToList();
+      return SelectEventsInRange(timeSignatures, timeSignature => timeSignature.Time);
+    }
+    catch (Exception ex)
+    {
+      Log.Info($"Failed to read the project's time signatures, using the part properties instead: {DescribeReflectionError(ex)}");
+      return [];
+    }
+  }
+
+  // NOTE: パートの開始時点で有効なイベントと、パートの範囲内のイベントだけを送る
+  private List<T> SelectEventsInRange<T>(List<T> events, Func<T, double> time)
+  {
+    var ordered = events.Where(e => double.IsFinite(time(e))).OrderBy(time).ToList();
+    var initialIndex = ordered.FindLastIndex(e => time(e) <= _startTime);
+    return ordered
+      .Where((e, i) => i >= initialIndex && time(e) <= _endTime)
+      .ToList();
+  }
+
+  private static string DescribeReflectionError(Exception ex)
+  {
+    var inner = ex is TargetInvocationException { InnerException: { } innerException } ? innerException : ex;
+    return $"{inner.GetType().Name}: {inner.Message}";
+  }
+
+  private static object GetMemberValue(object target, string name)
+  {
+    var property = target.GetType().GetProperty(name, BindingFlags.Public | BindingFlags.Instance)
+      ?? throw new MissingMemberException(target.GetType().FullName, name);
+    return property.GetValue(target)
+      ?? throw new InvalidOperationException($"{target.GetType().FullName}.{name} is null");
+  }
+
+  private static IEnumerable<object> GetEnumerableMember(object target, string name)
+  {
+    return GetMemberValue(target, name) is System.Collections.IEnumerable items
+      ? items.Cast<object>()
+      : throw new InvalidCastException($"{target.GetType().FullName}.{name} is not a collection");
+  }
+
+  private static double GetNumberMember(object target, string name)
+  {
+    return GetMemberValue(target, name) is IConvertible value
+      ? value.ToDouble(CultureInfo.InvariantCulture)
+      : throw new InvalidCastException($"{target.GetType().FullName}.{name} is not a number");
+  }
+
+  private static double TickToTime(object tempoManager, double tick)
+  {
+    var method = tempoManager.GetType().GetMethod("GetTime", BindingFlags.Public | BindingFlags.Instance, [typeof(double)])
+      ?? throw new MissingMethodException(tempoManager.GetType().FullName, "GetTime");
+    return method.Invoke(tempoManager, [tick]) is double time
+      ? time
+      : throw new InvalidCastException($"{tempoManager.GetType().FullName}.GetTime did not return a number");
+  }
+
   private static int? ResolveNeighborIndex(ISynthesisNote? note, IReadOnlyDictionary<ISynthesisNote, int> noteIndexMap)
   {
     if (note == null)
@@ -232,60 +326,32 @@ public unsafe sealed class NeutrinoTauSynthesisTask : ISynthesisTask
     return value.ToString();
   }
 
-  private static double ResolveNumericPartProperty(
-    PropertyObject partProperties,
-    string normalizedTargetKey,
-    bool roundToInteger = true)
-  {
-    foreach (var kv in partProperties.Map)
-    {
-      var key = kv.Key?.ToString();
-      if (string.IsNullOrWhiteSpace(key))
-      {
-        continue;
-      }
-
-      var normalized = key
-        .Replace("_", string.Empty, StringComparison.Ordinal)
-        .Replace(" ", string.Empty, StringComparison.Ordinal)
-        .Replace("-", string.Empty, StringComparison.Ordinal)
-        .ToLowerInvariant();
-      if (normalized != normalizedTargetKey)
-      {
-        continue;
-      }
-
-      var value = kv.Value;
-      if (value.ToDouble(out var d) && double.IsFinite(d))
-      {
-        return roundToInteger ? Math.Round(d, MidpointRounding.AwayFromZero) : d;
-      }
-
-      if (value.ToString(out var s)
-          && double.TryParse(s, NumberStyles.Float, CultureInfo.InvariantCulture, out var parsed)
-          && double.IsFinite(parsed))
-      {
-        return roundToInteger ? Math.Round(parsed, MidpointRounding.AwayFromZero) : parsed;
-      }
-    }
-
-    return 0.0;
-  }
-
   private sealed class SynthesisTaskPayload
   {
     public string VoiceId { get; init; } = string.Empty;
     public double StartTime { get; init; }
     public double EndTime { get; init; }
     public double Duration { get; init; }
-    public double StyleShift { get; init; }
-    public double WaveformStyleShift { get; init; }
-    public double PitchShiftCents { get; init; }
     public Dictionary<string, object?> PartProperties { get; init; } = [];
+    public List<TempoPayload> Tempos { get; init; } = [];
+    public List<TimeSignaturePayload> TimeSignatures { get; init; } = [];
     public List<SynthesisNotePayload> Notes { get; init; } = [];
     public PitchPayload Pitch { get; init; } = new();
   }
 
+  private sealed class TempoPayload
+  {
+    public double Time { get; init; }
+    public double Tempo { get; init; }
+  }
+
+  private sealed class TimeSignaturePayload
+  {
+    public double Time { get; init; }
+    public int Numerator { get; init; }
+    public int Denominator { get; init; }
+  }
+
   private sealed class SynthesisNotePayload
   {
     public double StartTime { get; init; }
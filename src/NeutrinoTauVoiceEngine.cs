@@ -15,9 +15,16 @@ public unsafe class NeutrinoTauVoiceEngine : IVoiceEngine
 
   private Native.CEngine* _nativeEngine;
   private readonly OrderedMap<string, VoiceSourceInfo> _voiceInfos = [];
+  // 音源を読み直すたびに増やし、音源ごとにキャッシュしたプロパティの定義を捨てる
+  private int _voiceGeneration;
 
   public IVoiceSource CreateVoiceSource(string id)
   {
+    // 起動後に追加された音源を使うプロジェクトを開いたときは、音源を探し直す
+    if (!string.IsNullOrEmpty(id) && !_voiceInfos.ContainsKey(id) && !RescanVoiceSources(out var error))
+    {
+      Log.Info($"Failed to rescan voice sources: {error}");
+    }
     return new NeutrinoTauVoiceSource(id, this);
   }
 
@@ -61,7 +68,12 @@ public unsafe class NeutrinoTauVoiceEngine : IVoiceEngine
     return true;
   }
 
-  private bool LoadVoiceSources(out string? error)
+  private bool RescanVoiceSources(out string? error)
+  {
+    return LoadVoiceSources(out error, rescan: true);
+  }
+
+  private bool LoadVoiceSources(out string? error, bool rescan = false)
   {
     if (_nativeEngine == null)
     {
@@ -70,7 +82,9 @@ public unsafe class NeutrinoTauVoiceEngine : IVoiceEngine
     }
 
     byte* errorPtr = null;
-    var voicesJsonPtr = Native.NativeMethods.neutrino_tau_load_voice_sources_json(_nativeEngine, &errorPtr);
+    var voicesJsonPtr = rescan
+        ? Native.NativeMethods.neutrino_tau_rescan_voice_sources_json(_nativeEngine, &errorPtr)
+        : Native.NativeMethods.neutrino_tau_load_voice_sources_json(_nativeEngine, &errorPtr);
     if (voicesJsonPtr == null)
     {
       error = errorPtr != null ? Marshal.PtrToStringUTF8((IntPtr)errorPtr) : "Failed to load voice sources.";
@@ -90,9 +104,14 @@ public unsafe class NeutrinoTauVoiceEngine : IVoiceEngine
         return false;
       }
 
-      var voices = JsonSerializer.Deserialize<List<NativeVoiceSource>>(voicesJson) ?? [];
+      var scan = JsonSerializer.Deserialize<NativeVoiceScan>(voicesJson) ?? new NativeVoiceScan();
+      foreach (var loadError in scan.Errors)
+      {
+        Log.Info($"Failed to load voice from {loadError.Path}: {loadError.Message}");
+      }
       _voiceInfos.Clear();
-      foreach (var voice in voices)
+      _voiceGeneration++;
+      foreach (var voice in scan.Voices)
       {
         if (string.IsNullOrWhiteSpace(voice.Id) || string.IsNullOrWhiteSpace(voice.Name))
         {
@@ -104,7 +123,7 @@ public unsafe class NeutrinoTauVoiceEngine : IVoiceEngine
             new VoiceSourceInfo
             {
               Name = voice.Name,
-              Description = voice.Description ?? string.Empty,
+              Description = BuildDescription(voice),
             }
         );
       }
@@ -127,13 +146,45 @@ public unsafe class NeutrinoTauVoiceEngine : IVoiceEngine
     }
   }
 
+  // VoiceSourceInfoには名前と説明しかないので、info.tomlの情報は説明にまとめる。立ち絵などの画像は表示できない
+  private static string BuildDescription(NativeVoiceSource voice)
+  {
+    var lines = new List<string>();
+    if (!string.IsNullOrWhiteSpace(voice.Description))
+    {
+      lines.Add(voice.Description);
+    }
+    void AddLine(string label, string? value)
+    {
+      if (!string.IsNullOrWhiteSpace(value))
+      {
+        lines.Add($"{label}: {value}");
+      }
+    }
+    AddLine("Language", voice.Language);
+    AddLine("Gender", voice.Gender);
+    AddLine("Range", voice.PitchRange is { } range ? $"{MidiToNoteName(range.Low)} - {MidiToNoteName(range.High)}" : null);
+    AddLine("Author", voice.Author);
+    AddLine("Version", voice.Version);
+    AddLine("License", voice.License);
+    AddLine("Web", voice.Web);
+    return string.Join("\n", lines);
+  }
+
+  private static readonly string[] NoteNames = ["C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B"];
+
+  private static string MidiToNoteName(int pitch)
+  {
+    return $"{NoteNames[pitch % 12]}{pitch / 12 - 1}";
+  }
+
   private sealed class NeutrinoTauVoiceSource(string id, NeutrinoTauVoiceEngine owner) : IVoiceSource
   {
     public string Name => string.IsNullOrEmpty(_id) ? DefaultVoiceSource.Name : _id;
     public string DefaultLyric { get; } = "a";
     public IReadOnlyOrderedMap<string, AutomationConfig> AutomationConfigs => AutomationConfigMap;
-    public IReadOnlyOrderedMap<string, IPropertyConfig> PartProperties => PartPropertyMap;
-    public IReadOnlyOrderedMap<string, IPropertyConfig> NoteProperties => NotePropertyMap;
+    public IReadOnlyOrderedMap<string, IPropertyConfig> PartProperties => LoadPropertyMaps().Part;
+    public IReadOnlyOrderedMap<string, IPropertyConfig> NoteProperties => LoadPropertyMaps().Note;
 
     public IReadOnlyList<SynthesisSegment<T>> Segment<T>(SynthesisSegment<T> segment) where T : ISynthesisNote
     {
@@ -147,16 +198,180 @@ public unsafe class NeutrinoTauVoiceEngine : IVoiceEngine
 
     private readonly string _id = id;
     private readonly NeutrinoTauVoiceEngine _owner = owner;
+    private (OrderedMap<string, IPropertyConfig> Part, OrderedMap<string, IPropertyConfig> Note)? _propertyMaps;
+    private int _propertyMapsGeneration;
+
+    private (OrderedMap<string, IPropertyConfig> Part, OrderedMap<string, IPropertyConfig> Note) LoadPropertyMaps()
+    {
+      if (_propertyMaps is { } cached && _propertyMapsGeneration == _owner._voiceGeneration)
+      {
+        return cached;
+      }
+      // 読み込めなかったときの既定の定義もキャッシュし、音源を読み直したときに作り直す
+      _propertyMaps = _owner.BuildPropertyMaps(_id);
+      _propertyMapsGeneration = _owner._voiceGeneration;
+      return _propertyMaps.Value;
+    }
   }
 
   private static readonly OrderedMap<string, AutomationConfig> AutomationConfigMap = [];
-  private static readonly OrderedMap<string, IPropertyConfig> PartPropertyMap = new()
+  // プロパティの定義（音源ごとの既定値・範囲を含む）はすべてネイティブ側から受け取る
+  private (OrderedMap<string, IPropertyConfig> Part, OrderedMap<string, IPropertyConfig> Note) BuildPropertyMaps(string voiceId)
+  {
+    if (!TryLoadPropertySchema(voiceId, out var schema, out var error) || schema == null)
+    {
+      Log.Info($"Failed to load property schema for {voiceId}: {error}");
+      if (!TryLoadDefaultPropertySchema(out schema, out error))
+      {
+        Log.Info($"Failed to load default property schema: {error}");
+      }
+    }
+    return (BuildPropertyMap(schema?.PartProperties ?? []), BuildPropertyMap(schema?.NoteProperties ?? []));
+  }
+
+  private static OrderedMap<string, IPropertyConfig> BuildPropertyMap(IEnumerable<NativePropertyDefinition> properties)
+  {
+    var map = new OrderedMap<string, IPropertyConfig>();
+    foreach (var property in properties)
     {
-        { "styleShift", new NumberConfig(0.0, -24.0, 24.0, true) },
-        { "waveformStyleShift", new NumberConfig(0.0, -24.0, 24.0, true) },
-        { "pitchShiftCents", new NumberConfig(0.0, -2400.0, 2400.0, true) },
+      if (ToPropertyConfig(property) is { } config)
+      {
+        map.Add(property.Name, config);
+      }
+      else
+      {
+        Log.Info($"Unknown property type {property.Type} for {property.Name}");
+      }
+    }
+    return map;
+  }
+
+  private static IPropertyConfig? ToPropertyConfig(NativePropertyDefinition property)
+  {
+    return property.Type switch
+    {
+      "number" => new NumberConfig(
+        property.Default.ValueKind == JsonValueKind.Number ? property.Default.GetDouble() : 0.0,
+        property.Min,
+        property.Max,
+        property.IsInteger),
+      "comboBox" => new ComboBoxConfig([.. property.Options]),
+      "checkBox" => new CheckBoxConfig(),
+      _ => null,
     };
-  private static readonly OrderedMap<string, IPropertyConfig> NotePropertyMap = [];
+  }
+
+  private bool TryLoadPropertySchema(string voiceId, out NativePropertySchema? schema, out string? error)
+  {
+    schema = null;
+    if (_nativeEngine == null)
+    {
+      error = "Engine is not initialized.";
+      return false;
+    }
+
+    var voiceIdBytes = System.Text.Encoding.UTF8.GetBytes(voiceId + "\0");
+    byte* errorPtr = null;
+    byte* schemaJsonPtr;
+    fixed (byte* voiceIdPtr = voiceIdBytes)
+    {
+      schemaJsonPtr = Native.NativeMethods.neutrino_tau_voice_property_schema_json(_nativeEngine, voiceIdPtr, &errorPtr);
+    }
+    return ReadPropertySchema(schemaJsonPtr, errorPtr, out schema, out error);
+  }
+
+  private static bool TryLoadDefaultPropertySchema(out NativePropertySchema? schema, out string? error)
+  {
+    byte* errorPtr = null;
+    var schemaJsonPtr = Native.NativeMethods.neutrino_tau_default_property_schema_json(&errorPtr);
+    return ReadPropertySchema(schemaJsonPtr, errorPtr, out schema, out error);
+  }
+
+  private static bool ReadPropertySchema(byte* schemaJsonPtr, byte* errorPtr, out NativePropertySchema? schema, out string? error)
+  {
+    schema = null;
+    if (schemaJsonPtr == null)
+    {
+      error = errorPtr != null ? Marshal.PtrToStringUTF8((IntPtr)errorPtr) : "Failed to load property schema.";
+      if (errorPtr != null)
+      {
+        Native.NativeMethods.neutrino_tau_free_c_string(errorPtr);
+      }
+      return false;
+    }
+
+    try
+    {
+      var schemaJson = Marshal.PtrToStringUTF8((IntPtr)schemaJsonPtr);
+      schema = schemaJson != null ? JsonSerializer.Deserialize<NativePropertySchema>(schemaJson) : null;
+      error = null;
+      return true;
+    }
+    catch (Exception ex)
+    {
+      error = $"Failed to parse property schema payload: {ex.Message}";
+      return false;
+    }
+    finally
+    {
+      Native.NativeMethods.neutrino_tau_free_c_string(schemaJsonPtr);
+      if (errorPtr != null)
+      {
+        Native.NativeMethods.neutrino_tau_free_c_string(errorPtr);
+      }
+    }
+  }
+  private sealed class NativePropertySchema
+  {
+    [JsonPropertyName("partProperties")]
+    public List<NativePropertyDefinition> PartProperties { get; init; } = [];
+
+    [JsonPropertyName("noteProperties")]
+    public List<NativePropertyDefinition> NoteProperties { get; init; } = [];
+  }
+
+  private sealed class NativePropertyDefinition
+  {
+    [JsonPropertyName("name")]
+    public string Name { get; init; } = string.Empty;
+
+    [JsonPropertyName("type")]
+    public string Type { get; init; } = string.Empty;
+
+    [JsonPropertyName("default")]
+    public JsonElement Default { get; init; }
+
+    [JsonPropertyName("min")]
+    public double Min { get; init; }
+
+    [JsonPropertyName("max")]
+    public double Max { get; init; }
+
+    [JsonPropertyName("isInteger")]
+    public bool IsInteger { get; init; }
+
+    [JsonPropertyName("options")]
+    public List<string> Options { get; init; } = [];
+  }
+
+  private sealed class NativeVoiceScan
+  {
+    [JsonPropertyName("voices")]
+    public List<NativeVoiceSource> Voices { get; init; } = [];
+
+    [JsonPropertyName("errors")]
+    public List<NativeVoiceLoadError> Errors { get; init; } = [];
+  }
+
+  private sealed class NativeVoiceLoadError
+  {
+    [JsonPropertyName("path")]
+    public string Path { get; init; } = string.Empty;
+
+    [JsonPropertyName("message")]
+    public string Message { get; init; } = string.Empty;
+  }
+
   private sealed class NativeVoiceSource
   {
     [JsonPropertyName("id")]
@@ -167,6 +382,36 @@ public unsafe class NeutrinoTauVoiceEngine : IVoiceEngine
 
     [JsonPropertyName("description")]
     public string? Description { get; init; }
+
+    [JsonPropertyName("language")]
+    public string? Language { get; init; }
+
+    [JsonPropertyName("gender")]
+    public string? Gender { get; init; }
+
+    [JsonPropertyName("author")]
+    public string? Author { get; init; }
+
+    [JsonPropertyName("version")]
+    public string? Version { get; init; }
+
+    [JsonPropertyName("license")]
+    public string? License { get; init; }
+
+    [JsonPropertyName("web")]
+    public string? Web { get; init; }
+
+    [JsonPropertyName("pitchRange")]
+    public NativePitchRange? PitchRange { get; init; }
+  }
+
+  private sealed class NativePitchRange
+  {
+    [JsonPropertyName("low")]
+    public int Low { get; init; }
+
+    [JsonPropertyName("high")]
+    public int High { get; init; }
   }
 
   private static readonly VoiceSourceInfo DefaultVoiceSource = new()